@@ -53,6 +53,15 @@ fn display_board(board: UserFloorRecordBoard, username: &str, buttons: u8) {
         board.board_type.to_string()
     );
     a_line(0);
+    let stats = board.stats();
+    println!(
+        "Played {}/{} (MAX COMBO or better: {}, Perfect: {})",
+        stats.total.played, stats.total.total, stats.total.max_combo_or_better, stats.total.perfect
+    );
+    if let Err(e) = board.check_count() {
+        println!("Warning: {}", e);
+    }
+    a_line(0);
     println!("Here's top 5 on this board:");
     display_song_result(board, 5);
     a_line(1);
@@ -1,14 +1,15 @@
 use v_archive_rs::{
-    register_record, ButtonMode, ChartType, LegacyExtCat, SongCatagory, UserChartRecord, UserToken,
+    ButtonMode, ChartType, Client, LegacyExtCat, SongCatagory, UserChartRecord, UserToken,
 };
 
 fn main() {
-    let user_num = 1;
-    let user_key = "95d6c422-52b4-4016-8587-38c46a2e7917";
-
-    let user_token = UserToken {
-        user_num,
-        user_token: user_key.to_owned(),
+    // Set `V_ARCHIVE_USER_NUM` and `V_ARCHIVE_USER_TOKEN` before running
+    let user_token = match UserToken::from_env() {
+        Ok(t) => t,
+        Err(error) => {
+            println!("Failed to load user token: {}", error);
+            return;
+        }
     };
 
     let record = UserChartRecord {
@@ -27,7 +28,12 @@ fn main() {
         updated_at: None,
     };
 
-    let req = register_record(user_token, record);
+    let client = Client::new().authenticate(user_token);
+    let (req, journal_error) = client.register_record(&record);
+
+    if let Some(error) = journal_error {
+        println!("Failed to write the journal: {}", error);
+    }
 
     match req {
         Ok(result) => {
@@ -0,0 +1,200 @@
+use crate::search::normalize_title;
+use crate::{SongSet, UserChartRecord};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const BUNDLED_ALIASES: &str = include_str!("../aliases/default.toml");
+
+/// Errors on loading an alias file
+#[derive(Debug)]
+pub enum AliasError {
+    /// Only `.toml` and `.json` files are available
+    UnknownFormat(String),
+    ParseError(String),
+    IOError(io::Error),
+}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFormat(p) => write!(f, "Unknown alias file format: {}", p),
+            Self::ParseError(m) => write!(f, "Alias file error: {}", m),
+            Self::IOError(e) => write!(f, "IO error: {}", e),
+        }
+    }
+}
+
+/// An alias which maps to several songs
+#[derive(Debug, Clone)]
+pub struct AliasConflict {
+    pub alias: String,
+    pub song_ids: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+struct AliasFile {
+    #[serde(default)]
+    aliases: BTreeMap<String, usize>,
+}
+
+/// A table of song aliases (nicknames, abbreviations, ...) to song IDs.
+///
+/// An alias file is TOML or JSON, like:
+/// ```toml
+/// [aliases]
+/// "glox" = 555
+/// "글록" = 555
+/// ```
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::AliasTable;
+/// #
+/// # fn main() {
+/// let mut aliases = AliasTable::bundled();
+/// aliases.load("my_aliases.toml").unwrap();
+///
+/// for c in aliases.conflicts() {
+///     println!("\"{}\" is for several songs: {:?}", c.alias, c.song_ids);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AliasTable {
+    /// Normalized alias to song IDs
+    aliases: HashMap<String, Vec<usize>>,
+}
+
+impl AliasTable {
+    /// An empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A table with aliases bundled on this crate
+    pub fn bundled() -> Self {
+        let mut table = Self::new();
+        table
+            .add_text(BUNDLED_ALIASES, false)
+            .expect("bundled aliases should be valid");
+        table
+    }
+
+    /// Add aliases from a file (`.toml` or `.json`)
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AliasError> {
+        let path = path.as_ref();
+        let is_json = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => false,
+            Some("json") => true,
+            _ => return Err(AliasError::UnknownFormat(path.display().to_string())),
+        };
+
+        let text = fs::read_to_string(path).map_err(AliasError::IOError)?;
+        self.add_text(&text, is_json)
+    }
+
+    fn add_text(&mut self, text: &str, is_json: bool) -> Result<(), AliasError> {
+        let file: AliasFile = if is_json {
+            serde_json::from_str(text).map_err(|e| AliasError::ParseError(e.to_string()))?
+        } else {
+            toml::from_str(text).map_err(|e| AliasError::ParseError(e.message().to_owned()))?
+        };
+
+        for (alias, song_id) in file.aliases {
+            self.add(&alias, song_id);
+        }
+
+        Ok(())
+    }
+
+    /// Add an alias for a song
+    pub fn add(&mut self, alias: &str, song_id: usize) {
+        let key = normalize_title(alias);
+        if key.is_empty() {
+            return;
+        }
+
+        let song_ids = self.aliases.entry(key).or_default();
+        if !song_ids.contains(&song_id) {
+            song_ids.push(song_id);
+        }
+    }
+
+    /// Song IDs for an alias (case/width-insensitive)
+    pub fn song_ids(&self, alias: &str) -> &[usize] {
+        self.aliases
+            .get(&normalize_title(alias))
+            .map(|ids| ids.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Aliases for a song
+    pub fn aliases_of(&self, song_id: usize) -> Vec<&str> {
+        let mut aliases: Vec<&str> = self
+            .aliases
+            .iter()
+            .filter(|(_, ids)| ids.contains(&song_id))
+            .map(|(a, _)| a.as_str())
+            .collect();
+        aliases.sort();
+        aliases
+    }
+
+    /// Aliases which map to several songs, sorted by alias
+    pub fn conflicts(&self) -> Vec<AliasConflict> {
+        let mut conflicts: Vec<AliasConflict> = self
+            .aliases
+            .iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|(alias, ids)| AliasConflict {
+                alias: alias.clone(),
+                song_ids: ids.clone(),
+            })
+            .collect();
+        conflicts.sort_by(|a, b| a.alias.cmp(&b.alias));
+        conflicts
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+/// If the title of a record is not a real title but an alias for only one song,
+/// change it to the real title (and song ID).
+pub(crate) fn apply_alias(record: &mut UserChartRecord, songs: &[SongSet], aliases: &AliasTable) {
+    if songs.iter().any(|s| s.content.title == record.title) {
+        return;
+    }
+
+    if let [song_id] = aliases.song_ids(&record.title) {
+        if let Some(song) = songs.iter().find(|s| s.content.song_id == *song_id) {
+            record.title = song.content.title.clone();
+            record.song_id = song.content.song_id;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alias_table_conflicts() {
+        let mut aliases = AliasTable::bundled();
+        assert_eq!(aliases.song_ids("GLOX"), &[555]);
+
+        aliases
+            .add_text(r#"{"aliases": {"Glox": 556, "urban": 3}}"#, true)
+            .unwrap();
+
+        assert_eq!(aliases.song_ids("urban"), &[3]);
+        let conflicts = aliases.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].alias, "glox".to_string());
+        assert_eq!(conflicts[0].song_ids, vec![555, 556]);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{ButtonMode, Chart, ChartRef, Song, SongSet, UserChartRecord, UserTierRecordTable};
+
+/// Numbers of records for tier points
+const TOP_RECORDS: usize = 50;
+
+/// A top record and how much it counts for tier points
+#[derive(Debug, Clone)]
+pub struct RecordContribution<'a> {
+    pub record: &'a UserChartRecord,
+    /// Rank on top records, from 1
+    pub rank: usize,
+    pub rating: f64,
+    /// Rate of the rating on the sum of top records (`0.0` to `1.0`)
+    pub share: f64,
+    /// Rating left to `maximum_rating` (`None` without maximum rating)
+    pub potential_gain: Option<f64>,
+}
+
+/// A chart outside top records which can displace the weakest one
+#[derive(Debug, Clone)]
+pub struct DisplaceCandidate<'s> {
+    pub song: &'s Song,
+    pub chart: &'s Chart,
+    /// The estimated minimum accuracy to get over the cutoff
+    pub required_acc: f64,
+    /// Rating over the cutoff at the best (maximum rating minus cutoff)
+    pub max_gain: f64,
+}
+
+/// A breakdown of `UserTierRecordTable`, from `UserTierRecordTable::breakdown()`.
+///
+/// V-Archive doesn't publish the rating formula, so accuracy for a rating is estimated
+/// from the table's own records (as a rate of maximum rating), assuming 100% accuracy
+/// gets the maximum rating.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{load_user_tier, Catalog};
+/// #
+/// # fn main() {
+/// let catalog = Catalog::load().unwrap();
+/// let table = load_user_tier("내꺼", 6).unwrap();
+/// let breakdown = table.breakdown();
+///
+/// println!("Cutoff: {:.3}, gain left: {:.3}", breakdown.cutoff, breakdown.total_gain());
+/// for c in breakdown.candidates(&catalog).iter().take(10) {
+///     println!("{} {}: {:.2}%", c.song.title, c.chart.chart_type, c.required_acc);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TierBreakdown<'a> {
+    /// Top records, from the highest rating
+    pub contributions: Vec<RecordContribution<'a>>,
+    pub rating_sum: f64,
+    /// Rating to get in top records: the weakest rating if the list is full, `0.0` otherwise
+    pub cutoff: f64,
+    /// `(accuracy, rate of maximum rating)`, in ascending order
+    curve: Vec<(f64, f64)>,
+    buttons: Vec<ButtonMode>,
+}
+
+impl TierBreakdown<'_> {
+    /// Top records are full (50 records)
+    pub fn is_full(&self) -> bool {
+        self.contributions.len() >= TOP_RECORDS
+    }
+
+    /// Sum of potential gains of top records
+    pub fn total_gain(&self) -> f64 {
+        self.contributions
+            .iter()
+            .filter_map(|c| c.potential_gain)
+            .sum()
+    }
+
+    /// The estimated minimum accuracy to get over the cutoff, on a chart with the maximum rating.
+    ///
+    /// It's `None` if the maximum rating is not over the cutoff, and `0.0` if top records
+    /// are not full (any record gets in). Below the lowest accuracy on records,
+    /// the lowest one is given (the real one may be lower).
+    pub fn required_acc(&self, maximum_rating: f64) -> Option<f64> {
+        if !self.is_full() {
+            return Some(0.0);
+        }
+        if maximum_rating <= self.cutoff {
+            return None;
+        }
+
+        let rate = self.cutoff / maximum_rating;
+        let (first_acc, first_rate) = self.curve[0];
+        if rate <= first_rate {
+            return Some(first_acc);
+        }
+        self.curve.windows(2).find_map(|w| {
+            let ((acc0, rate0), (acc1, rate1)) = (w[0], w[1]);
+            if rate > rate1 {
+                return None;
+            }
+            if rate1 <= rate0 {
+                return Some(acc1);
+            }
+            Some(acc0 + (acc1 - acc0) * (rate - rate0) / (rate1 - rate0))
+        })
+    }
+
+    /// Charts not on top records which can get over the cutoff, from the easiest
+    /// (lowest required accuracy). Only charts for button modes on top records are given.
+    pub fn candidates<'s>(&self, songs: &'s [SongSet]) -> Vec<DisplaceCandidate<'s>> {
+        let on_top: Vec<ChartRef> = self
+            .contributions
+            .iter()
+            .map(|c| c.record.chart_ref())
+            .collect();
+
+        let mut candidates: Vec<DisplaceCandidate<'s>> = songs
+            .iter()
+            .flat_map(|s| s.charts.iter().map(move |c| (&s.content, c)))
+            .filter(|(_, c)| self.buttons.is_empty() || self.buttons.contains(&c.button))
+            .filter(|(s, c)| !on_top.contains(&ChartRef::of(s, c)))
+            .filter_map(|(song, chart)| {
+                let maximum = chart.rating?;
+                Some(DisplaceCandidate {
+                    song,
+                    chart,
+                    required_acc: self.required_acc(maximum)?,
+                    max_gain: maximum - self.cutoff,
+                })
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            a.required_acc
+                .total_cmp(&b.required_acc)
+                .then(b.max_gain.total_cmp(&a.max_gain))
+        });
+        candidates
+    }
+}
+
+impl UserTierRecordTable {
+    /// Contributions of top records, the cutoff and gains left
+    pub fn breakdown(&self) -> TierBreakdown<'_> {
+        let mut records: Vec<&UserChartRecord> = self.top_records.iter().collect();
+        records.sort_by(|a, b| {
+            b.user_rating
+                .unwrap_or(0.0)
+                .total_cmp(&a.user_rating.unwrap_or(0.0))
+        });
+        let rating_sum: f64 = records.iter().filter_map(|r| r.user_rating).sum();
+
+        let contributions: Vec<RecordContribution> = records
+            .into_iter()
+            .enumerate()
+            .map(|(i, record)| {
+                let rating = record.user_rating.unwrap_or(0.0);
+                RecordContribution {
+                    record,
+                    rank: i + 1,
+                    rating,
+                    share: if rating_sum > 0.0 {
+                        rating / rating_sum
+                    } else {
+                        0.0
+                    },
+                    potential_gain: record.maximum_rating.map(|m| (m - rating).max(0.0)),
+                }
+            })
+            .collect();
+        let cutoff = if contributions.len() >= TOP_RECORDS {
+            contributions.last().map_or(0.0, |c| c.rating)
+        } else {
+            0.0
+        };
+
+        // Rate of maximum rating never goes down on higher accuracy
+        let mut curve: Vec<(f64, f64)> = self
+            .top_records
+            .iter()
+            .filter_map(|r| match (r.acc_rate, r.user_rating, r.maximum_rating) {
+                (Some(acc), Some(rating), Some(m)) if m > 0.0 => Some((acc, rating / m)),
+                _ => None,
+            })
+            .collect();
+        curve.push((100.0, 1.0));
+        curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut highest: f64 = 0.0;
+        for point in &mut curve {
+            highest = highest.max(point.1);
+            point.1 = highest;
+        }
+
+        let mut buttons: Vec<ButtonMode> =
+            self.top_records.iter().map(|r| r.button.clone()).collect();
+        buttons.sort();
+        buttons.dedup();
+
+        TierBreakdown {
+            contributions,
+            rating_sum,
+            cutoff,
+            curve,
+            buttons,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{record, songs};
+    use crate::ChartType;
+
+    fn tier_table(count: usize) -> UserTierRecordTable {
+        let mut table = UserTierRecordTable::new();
+        for i in 0..count {
+            // Rating is 20 * (acc - 90) of 200 on these records
+            let acc = 95.0 + i as f64 * 0.1;
+            let mut record = record(i, ChartType::Sc)
+                .with_acc(acc)
+                .with_rating(20.0 * (acc - 90.0));
+            record.maximum_rating = Some(200.0);
+            table.top_records.push(record);
+        }
+        table
+    }
+
+    #[test]
+    fn tier_breakdown() {
+        let table = tier_table(50);
+        let breakdown = table.breakdown();
+        assert!(breakdown.is_full());
+        assert_eq!(breakdown.contributions[0].rank, 1);
+        assert!((breakdown.contributions[0].rating - 198.0).abs() < 1e-9);
+        assert!((breakdown.cutoff - 100.0).abs() < 1e-9);
+        assert!((breakdown.contributions[49].potential_gain.unwrap() - 100.0).abs() < 1e-9);
+        let shares: f64 = breakdown.contributions.iter().map(|c| c.share).sum();
+        assert!((shares - 1.0).abs() < 1e-9);
+
+        assert!((breakdown.required_acc(150.0).unwrap() - 96.6667).abs() < 1e-3);
+        assert_eq!(breakdown.required_acc(100.0), None);
+        // Lower than any record, so the lowest accuracy is given
+        assert!((breakdown.required_acc(250.0).unwrap() - 95.0).abs() < 1e-9);
+        assert!((breakdown.required_acc(101.0).unwrap() - 99.95).abs() < 0.1);
+
+        let songs = songs();
+        let candidates = breakdown.candidates(&songs);
+        let charts: Vec<ChartType> = candidates
+            .iter()
+            .map(|c| c.chart.chart_type.clone())
+            .collect();
+        // SC of song 16 is on top records, 8B is not on them, and NM can't get over the cutoff
+        assert_eq!(charts, vec![ChartType::Sc, ChartType::Hard]);
+        assert_eq!(candidates[0].song.song_id, 555);
+        assert!((candidates[0].max_gain - 110.5).abs() < 1e-9);
+
+        let table = tier_table(10);
+        let breakdown = table.breakdown();
+        assert!(!breakdown.is_full());
+        assert_eq!(breakdown.cutoff, 0.0);
+        assert_eq!(breakdown.required_acc(90.0), Some(0.0));
+    }
+}
@@ -0,0 +1,365 @@
+use crate::search::{normalize_title, resolution_of};
+use crate::{
+    all_songs, APIError, AliasTable, ButtonMode, Chart, ChartQuery, ChartRef, OwnedContent, Song,
+    SongCatagory, SongResolution, SongSet,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A position of a chart: `(song index, chart index)`
+type ChartPosition = (usize, usize);
+
+#[derive(Debug)]
+struct CatalogData {
+    songs: Vec<SongSet>,
+    by_id: HashMap<usize, usize>,
+    /// Normalized title to song indexes
+    by_title: HashMap<String, Vec<usize>>,
+    by_category: HashMap<SongCatagory, Vec<usize>>,
+    charts: HashMap<ChartRef, ChartPosition>,
+    by_level: BTreeMap<u8, Vec<ChartPosition>>,
+    /// Charts with floor, sorted by floor
+    by_floor: Vec<(f64, ChartPosition)>,
+}
+
+/// A song list (from `all_songs()`) with indexes, for fast lookup.
+///
+/// Cloning is cheap (the data is shared), so it can be kept on a server's state.
+/// It derefs to `[SongSet]`, so functions for a song list also take it.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{ButtonMode, Catalog, ChartRef};
+/// #
+/// # fn main() {
+/// let catalog = Catalog::load().unwrap();
+///
+/// let chart: ChartRef = "555/6B/SC".parse().unwrap();
+/// if let Some((song, chart)) = catalog.chart(&chart) {
+///     println!("{}: Lv.{} ({:?})", song.title, chart.level, chart.floor);
+/// }
+///
+/// for (song, chart) in catalog.charts_by_level(Some(ButtonMode::Six), 14, 15) {
+///     println!("{} {}", song.title, chart.chart_type);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    data: Arc<CatalogData>,
+}
+
+impl Catalog {
+    /// Build indexes for a song list
+    pub fn new(songs: Vec<SongSet>) -> Self {
+        let mut by_id = HashMap::new();
+        let mut by_title: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_category: HashMap<SongCatagory, Vec<usize>> = HashMap::new();
+        let mut charts = HashMap::new();
+        let mut by_level: BTreeMap<u8, Vec<ChartPosition>> = BTreeMap::new();
+        let mut by_floor = Vec::new();
+
+        for (song_index, song) in songs.iter().enumerate() {
+            by_id.insert(song.content.song_id, song_index);
+            by_title
+                .entry(normalize_title(&song.content.title))
+                .or_default()
+                .push(song_index);
+            by_category
+                .entry(song.content.song_cat.clone())
+                .or_default()
+                .push(song_index);
+
+            for (chart_index, chart) in song.charts.iter().enumerate() {
+                let position = (song_index, chart_index);
+                charts.insert(ChartRef::of(&song.content, chart), position);
+                by_level.entry(chart.level).or_default().push(position);
+                if let Some(floor) = chart.floor {
+                    by_floor.push((floor, position));
+                }
+            }
+        }
+        by_floor.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self {
+            data: Arc::new(CatalogData {
+                songs,
+                by_id,
+                by_title,
+                by_category,
+                charts,
+                by_level,
+                by_floor,
+            }),
+        }
+    }
+
+    /// A new catalog only with songs the player owns
+    pub fn restrict(&self, owned: &OwnedContent) -> Self {
+        Self::new(
+            self.songs()
+                .iter()
+                .filter(|s| owned.owns_song(&s.content))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Load a song list from server, and build a catalog
+    pub fn load() -> Result<Self, APIError> {
+        Ok(Self::new(all_songs()?))
+    }
+
+    pub fn songs(&self) -> &[SongSet] {
+        &self.data.songs
+    }
+
+    pub fn song(&self, song_id: usize) -> Option<&SongSet> {
+        self.data.by_id.get(&song_id).map(|i| &self.data.songs[*i])
+    }
+
+    /// Songs with the title (case/width-insensitive). Aliases are not looked up.
+    pub fn songs_by_title(&self, title: &str) -> Vec<&SongSet> {
+        self.songs_at(self.data.by_title.get(&normalize_title(title)))
+    }
+
+    /// Same as `resolve_song()` on this catalog, with the title index. Aliases are not looked up;
+    /// use `Catalog::resolve_song_with_aliases()` for them.
+    pub fn resolve_song(&self, title: &str) -> SongResolution<'_> {
+        self.resolve_song_inner(title, None)
+    }
+
+    /// Same as `resolve_song_with_aliases()` on this catalog
+    pub fn resolve_song_with_aliases(
+        &self,
+        title: &str,
+        aliases: &AliasTable,
+    ) -> SongResolution<'_> {
+        self.resolve_song_inner(title, Some(aliases))
+    }
+
+    pub fn songs_in(&self, category: &SongCatagory) -> Vec<&SongSet> {
+        self.songs_at(self.data.by_category.get(category))
+    }
+
+    /// Categories on the catalog, in order of category code (e. g. `R`, `VE3`)
+    pub fn categories(&self) -> Vec<&SongCatagory> {
+        let mut categories: Vec<&SongCatagory> = self.data.by_category.keys().collect();
+        categories.sort_by_cached_key(|c| c.to_string());
+        categories
+    }
+
+    pub fn chart(&self, chart: &ChartRef) -> Option<(&Song, &Chart)> {
+        self.data.charts.get(chart).map(|p| self.chart_at(*p))
+    }
+
+    pub fn contains_chart(&self, chart: &ChartRef) -> bool {
+        self.data.charts.contains_key(chart)
+    }
+
+    /// Charts with a level from `min` to `max`, only for `button` if given
+    pub fn charts_by_level(
+        &self,
+        button: Option<ButtonMode>,
+        min: u8,
+        max: u8,
+    ) -> Vec<(&Song, &Chart)> {
+        if min > max {
+            return Vec::new();
+        }
+
+        self.data
+            .by_level
+            .range(min..=max)
+            .flat_map(|(_, positions)| positions.iter())
+            .map(|p| self.chart_at(*p))
+            .filter(|(_, c)| button.as_ref().is_none_or(|b| &c.button == b))
+            .collect()
+    }
+
+    /// Charts with a floor from `min` to `max`, in order of floor. Charts without floor are not
+    /// included.
+    pub fn charts_by_floor(&self, min: f64, max: f64) -> Vec<(&Song, &Chart)> {
+        let by_floor = &self.data.by_floor;
+        let start = by_floor.partition_point(|(f, _)| *f < min);
+        let end = by_floor.partition_point(|(f, _)| *f <= max);
+
+        by_floor[start..end.max(start)]
+            .iter()
+            .map(|(_, p)| self.chart_at(*p))
+            .collect()
+    }
+
+    /// Same as `ChartQuery::run()` on this catalog
+    pub fn query(&self, query: &ChartQuery) -> Vec<(&Song, &Chart)> {
+        query.run(self.songs())
+    }
+
+    /// Number of songs
+    pub fn song_count(&self) -> usize {
+        self.data.songs.len()
+    }
+
+    /// Number of charts
+    pub fn chart_count(&self) -> usize {
+        self.data.charts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.songs.is_empty()
+    }
+
+    fn resolve_song_inner(&self, title: &str, aliases: Option<&AliasTable>) -> SongResolution<'_> {
+        let same = self.songs_by_title(title);
+
+        let exact: Vec<&SongSet> = same
+            .iter()
+            .copied()
+            .filter(|s| s.content.title == title)
+            .collect();
+        if let Some(r) = resolution_of(exact) {
+            return r;
+        }
+
+        if let Some(aliases) = aliases {
+            let mut indexes: Vec<usize> = aliases
+                .song_ids(title)
+                .iter()
+                .filter_map(|id| self.data.by_id.get(id).copied())
+                .collect();
+            indexes.sort_unstable();
+            if let Some(r) = resolution_of(self.songs_at(Some(&indexes))) {
+                return r;
+            }
+        }
+
+        let query = normalize_title(title);
+        if query.is_empty() {
+            return SongResolution::NotFound;
+        }
+        if let Some(r) = resolution_of(same) {
+            return r;
+        }
+
+        // Only for the start of title, every title is checked
+        let mut indexes: Vec<usize> = self
+            .data
+            .by_title
+            .iter()
+            .filter(|(t, _)| t.starts_with(&query))
+            .flat_map(|(_, list)| list.iter().copied())
+            .collect();
+        indexes.sort_unstable();
+
+        resolution_of(self.songs_at(Some(&indexes))).unwrap_or(SongResolution::NotFound)
+    }
+
+    fn songs_at(&self, indexes: Option<&Vec<usize>>) -> Vec<&SongSet> {
+        indexes
+            .map(|list| list.iter().map(|i| &self.data.songs[*i]).collect())
+            .unwrap_or_default()
+    }
+
+    fn chart_at(&self, (song, chart): ChartPosition) -> (&Song, &Chart) {
+        let song = &self.data.songs[song];
+        (&song.content, &song.charts[chart])
+    }
+}
+
+impl From<Vec<SongSet>> for Catalog {
+    fn from(songs: Vec<SongSet>) -> Self {
+        Self::new(songs)
+    }
+}
+
+impl Deref for Catalog {
+    type Target = [SongSet];
+
+    fn deref(&self) -> &[SongSet] {
+        self.songs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::songs;
+    use crate::ChartType;
+
+    #[test]
+    fn catalog_lookup() {
+        let catalog = Catalog::new(songs());
+        let shared = catalog.clone();
+        assert!(Arc::ptr_eq(&catalog.data, &shared.data));
+        assert_eq!((catalog.song_count(), catalog.chart_count()), (15, 22));
+
+        assert_eq!(catalog.song(555).unwrap().content.title, "Gloxinia");
+        assert_eq!(catalog.songs_by_title("GLOXINIA").len(), 1);
+        assert_eq!(catalog.songs_in(&SongCatagory::from("R")).len(), 8);
+        let categories: Vec<String> = catalog.categories().iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            categories,
+            vec!["CY", "ES", "GG", "P1", "P3", "R", "VE", "VE3"]
+        );
+
+        let chart = ChartRef::new(555, ButtonMode::Six, ChartType::Sc);
+        assert_eq!(catalog.chart(&chart).map(|(_, c)| c.level), Some(14));
+        assert!(!catalog.contains_chart(&ChartRef::new(555, ButtonMode::Four, ChartType::Sc)));
+
+        assert_eq!(catalog.charts_by_level(None, 14, 15).len(), 8);
+        assert_eq!(
+            catalog.charts_by_level(Some(ButtonMode::Six), 14, 15).len(),
+            6
+        );
+        let floors: Vec<Option<f64>> = catalog
+            .charts_by_floor(13.5, 14.8)
+            .iter()
+            .map(|(_, c)| c.floor)
+            .collect();
+        assert_eq!(floors, vec![Some(13.5), Some(14.2), Some(14.2), Some(14.8)]);
+        assert!(catalog.charts_by_floor(15.0, 14.0).is_empty());
+
+        // Functions for a song list take a catalog
+        assert_eq!(ChartRef::resolve("Gloxinia 6B SC", &catalog), Ok(chart));
+    }
+
+    #[test]
+    fn catalog_resolve_song() {
+        let songs = songs();
+        let catalog = Catalog::new(songs.clone());
+        let mut aliases = AliasTable::new();
+        aliases.add("글록", 555);
+
+        let ids = |r: SongResolution| match r {
+            SongResolution::Found(s) => vec![s.content.song_id],
+            SongResolution::Ambiguous(list) => list.iter().map(|s| s.content.song_id).collect(),
+            SongResolution::NotFound => Vec::new(),
+        };
+
+        // Same as `resolve_song()` on the song list
+        for title in [
+            "Airwave",
+            "airwave",
+            "AIR",
+            "alone",
+            "ｕｒｂａｎ",
+            "Night/",
+            "x",
+            " ",
+        ] {
+            assert_eq!(
+                ids(catalog.resolve_song(title)),
+                ids(crate::resolve_song(&songs, title)),
+                "{}",
+                title
+            );
+        }
+
+        assert_eq!(ids(catalog.resolve_song("글록")), Vec::<usize>::new());
+        assert_eq!(
+            ids(catalog.resolve_song_with_aliases("글록", &aliases)),
+            vec![555]
+        );
+        assert!(catalog.songs_by_title("글록").is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+use crate::search::{resolve_song, resolve_song_with_aliases};
+use crate::{
+    AliasTable, ButtonMode, Chart, ChartType, Song, SongResolution, SongSet, UserChartRecord,
+};
+use std::fmt;
+use std::str::FromStr;
+
+/// Errors on parsing `ChartRef`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChartRefError {
+    /// Not like `555/6B/SC` or `Gloxinia 6B SC`
+    InvalidNotation(String),
+    InvalidButton(String),
+    InvalidChartType(String),
+    /// A title is given, but no song list (use `ChartRef::resolve()`)
+    NeedsSongList(String),
+    CannotFindSong(String),
+    FoundSeveralSongs(String),
+    /// The song doesn't have the chart
+    CannotFindChart(ChartRef),
+}
+
+impl fmt::Display for ChartRefError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidNotation(t) => write!(f, "Invalid chart notation: {}", t),
+            Self::InvalidButton(b) => write!(f, "Invalid button: {}", b),
+            Self::InvalidChartType(t) => write!(f, "Invalid chart type: {}", t),
+            Self::NeedsSongList(t) => write!(f, "Needs a song list for a title: {}", t),
+            Self::CannotFindSong(t) => write!(f, "Cannot find a song: {}", t),
+            Self::FoundSeveralSongs(t) => write!(f, "Found several songs: {}", t),
+            Self::CannotFindChart(c) => write!(f, "Cannot find a chart: {}", c),
+        }
+    }
+}
+
+/// A value for a chart: a song ID, a button mode and a chart type.
+///
+/// It's written like `555/6B/SC`. With a song list, a title is also available, like
+/// `Gloxinia 6B SC` (see `ChartRef::resolve()`).
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{all_songs, ChartRef};
+/// #
+/// # fn main() {
+/// let chart: ChartRef = "555/6B/SC".parse().unwrap();
+/// assert_eq!(chart.to_string(), "555/6B/SC");
+///
+/// let songs = all_songs().unwrap();
+/// let chart = ChartRef::resolve("Gloxinia 6B SC", &songs).unwrap();
+/// println!("{}", chart.format_with_title(&songs));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChartRef {
+    pub song_id: usize,
+    pub button: ButtonMode,
+    pub chart_type: ChartType,
+}
+
+impl ChartRef {
+    pub fn new(song_id: usize, button: ButtonMode, chart_type: ChartType) -> Self {
+        Self {
+            song_id,
+            button,
+            chart_type,
+        }
+    }
+
+    /// `ChartRef` for a chart of a song
+    pub fn of(song: &Song, chart: &Chart) -> Self {
+        Self::new(song.song_id, chart.button.clone(), chart.chart_type.clone())
+    }
+
+    /// Parse `555/6B/SC` or `Gloxinia 6B SC` with a song list (from `all_songs()`).
+    /// A title is found by `resolve_song()`, and the song should have the chart.
+    pub fn resolve(text: &str, songs: &[SongSet]) -> Result<Self, ChartRefError> {
+        Self::resolve_inner(text, songs, None)
+    }
+
+    /// Same as `ChartRef::resolve()`, but a title is found by `resolve_song_with_aliases()`
+    pub fn resolve_with_aliases(
+        text: &str,
+        songs: &[SongSet],
+        aliases: &AliasTable,
+    ) -> Result<Self, ChartRefError> {
+        Self::resolve_inner(text, songs, Some(aliases))
+    }
+
+    fn resolve_inner(
+        text: &str,
+        songs: &[SongSet],
+        aliases: Option<&AliasTable>,
+    ) -> Result<Self, ChartRefError> {
+        let (song, button, chart_type) = split_notation(text)?;
+
+        let song_set = match song.parse::<usize>() {
+            Ok(song_id) => songs
+                .iter()
+                .find(|s| s.content.song_id == song_id)
+                .ok_or(ChartRefError::CannotFindSong(song.to_owned()))?,
+            Err(_) => match aliases.map_or_else(
+                || resolve_song(songs, song),
+                |a| resolve_song_with_aliases(songs, song, a),
+            ) {
+                SongResolution::Found(s) => s,
+                SongResolution::Ambiguous(_) => {
+                    return Err(ChartRefError::FoundSeveralSongs(song.to_owned()))
+                }
+                SongResolution::NotFound => {
+                    return Err(ChartRefError::CannotFindSong(song.to_owned()))
+                }
+            },
+        };
+
+        let chart = Self::new(song_set.content.song_id, button, chart_type);
+        match song_set.chart(&chart) {
+            Some(_) => Ok(chart),
+            None => Err(ChartRefError::CannotFindChart(chart)),
+        }
+    }
+
+    /// Write like `Gloxinia 6B SC`. If the song is not on the list, same as `to_string()`.
+    pub fn format_with_title(&self, songs: &[SongSet]) -> String {
+        match songs.iter().find(|s| s.content.song_id == self.song_id) {
+            Some(s) => format!("{} {}B {}", s.content.title, self.button, self.chart_type),
+            None => self.to_string(),
+        }
+    }
+
+    /// Whether a record is for this chart
+    pub fn is_for(&self, record: &UserChartRecord) -> bool {
+        record.song_id == self.song_id
+            && record.button == self.button
+            && record.chart_type == self.chart_type
+    }
+}
+
+impl FromStr for ChartRef {
+    type Err = ChartRefError;
+
+    /// Parse `555/6B/SC`. For a title, use `ChartRef::resolve()`.
+    fn from_str(s: &str) -> Result<Self, ChartRefError> {
+        let (song, button, chart_type) = split_notation(s)?;
+        let song_id = song
+            .parse()
+            .map_err(|_| ChartRefError::NeedsSongList(song.to_owned()))?;
+
+        Ok(Self::new(song_id, button, chart_type))
+    }
+}
+
+impl fmt::Display for ChartRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}B/{}", self.song_id, self.button, self.chart_type)
+    }
+}
+
+impl From<&UserChartRecord> for ChartRef {
+    fn from(record: &UserChartRecord) -> Self {
+        Self::new(
+            record.song_id,
+            record.button.clone(),
+            record.chart_type.clone(),
+        )
+    }
+}
+
+/// Split a notation from the right, so a title can have `/` or spaces.
+/// `Title/6B/SC` is tried first, then `Title 6B SC`.
+fn split_notation(text: &str) -> Result<(&str, ButtonMode, ChartType), ChartRefError> {
+    let text = text.trim();
+    let slashed = split_parts(text.rsplitn(3, '/').collect());
+    if slashed.is_ok() || !text.contains(char::is_whitespace) {
+        return slashed;
+    }
+
+    match split_parts(text.rsplitn(3, char::is_whitespace).collect()) {
+        Err(ChartRefError::InvalidNotation(_)) => slashed,
+        spaced => spaced,
+    }
+}
+
+fn split_parts(parts: Vec<&str>) -> Result<(&str, ButtonMode, ChartType), ChartRefError> {
+    match parts.as_slice() {
+        [chart_type, button, song] if !song.trim().is_empty() => Ok((
+            song.trim(),
+            parse_button(button.trim())?,
+            parse_chart_type(chart_type.trim())?,
+        )),
+        _ => Err(ChartRefError::InvalidNotation(parts.join("/"))),
+    }
+}
+
+fn parse_button(text: &str) -> Result<ButtonMode, ChartRefError> {
+    let number = text.strip_suffix(['B', 'b']).unwrap_or(text);
+    match number.parse::<u8>().map(ButtonMode::from) {
+        Ok(ButtonMode::Other(_)) | Err(_) => Err(ChartRefError::InvalidButton(text.to_owned())),
+        Ok(b) => Ok(b),
+    }
+}
+
+fn parse_chart_type(text: &str) -> Result<ChartType, ChartRefError> {
+    match ChartType::from(text.to_uppercase().as_str()) {
+        ChartType::Other(_) => Err(ChartRefError::InvalidChartType(text.to_owned())),
+        t => Ok(t),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::songs;
+    use std::collections::HashSet;
+
+    #[test]
+    fn chart_ref_notation() {
+        let chart: ChartRef = "555/6B/SC".parse().unwrap();
+        assert_eq!(chart, ChartRef::new(555, ButtonMode::Six, ChartType::Sc));
+        assert_eq!(chart.to_string(), "555/6B/SC");
+        assert_eq!("555/6/sc".parse::<ChartRef>(), Ok(chart.clone()));
+        assert!(matches!(
+            "Gloxinia 6B SC".parse::<ChartRef>(),
+            Err(ChartRefError::NeedsSongList(_))
+        ));
+        assert!(matches!(
+            "555/7B/SC".parse::<ChartRef>(),
+            Err(ChartRefError::InvalidButton(_))
+        ));
+
+        let songs = songs();
+        assert_eq!(
+            ChartRef::resolve("gloxinia 6B SC", &songs),
+            Ok(chart.clone())
+        );
+        assert_eq!(chart.format_with_title(&songs), "Gloxinia 6B SC");
+        assert!(matches!(
+            ChartRef::resolve("Gloxinia 4B SC", &songs),
+            Err(ChartRefError::CannotFindChart(_))
+        ));
+        assert!(matches!(
+            ChartRef::resolve("Alone 4B NM", &songs),
+            Err(ChartRefError::FoundSeveralSongs(_))
+        ));
+
+        let set: HashSet<ChartRef> = [chart.clone(), chart.clone()].into_iter().collect();
+        assert_eq!(set.len(), 1);
+        assert!(ChartRef::new(555, ButtonMode::Four, ChartType::Sc) < chart);
+
+        let song = songs.iter().find(|s| s.content.song_id == 555).unwrap();
+        assert_eq!(song.chart(&chart).map(|c| c.level), Some(14));
+        assert_eq!(song.chart_refs().len(), 4);
+        assert!(song.chart_refs().contains(&chart));
+    }
+
+    #[test]
+    fn chart_ref_title_with_slash() {
+        let songs = songs();
+        let chart = ChartRef::new(8, ButtonMode::Four, ChartType::Normal);
+
+        assert_eq!(
+            ChartRef::resolve("Night/Day/4B/NM", &songs),
+            Ok(chart.clone())
+        );
+        assert_eq!(ChartRef::resolve("Night/Day 4B NM", &songs), Ok(chart));
+        assert!(matches!(
+            "555/6B".parse::<ChartRef>(),
+            Err(ChartRefError::InvalidNotation(_))
+        ));
+    }
+
+    #[test]
+    fn chart_ref_with_aliases() {
+        let songs = songs();
+        let mut aliases = AliasTable::new();
+        aliases.add("글록", 555);
+
+        assert!(ChartRef::resolve("글록/6B/SC", &songs).is_err());
+        assert_eq!(
+            ChartRef::resolve_with_aliases("글록/6B/SC", &songs, &aliases),
+            Ok(ChartRef::new(555, ButtonMode::Six, ChartType::Sc))
+        );
+    }
+}
@@ -0,0 +1,266 @@
+#[cfg(feature = "write")]
+use crate::{
+    register::{register_record, register_records, safe_register_record, send_record, SentRecord},
+    APIRegisterResult, AliasTable, Journal, RegisterOptions, RegisterReport, SafeRegisterResult,
+    UserChartRecord, UserToken,
+};
+use crate::{
+    APIError, Catalog, Dlc, SongSet, SongUserRecord, Tier, UserFloorRecordBoard,
+    UserTierRecordTable,
+};
+#[cfg(feature = "write")]
+use std::io;
+#[cfg(feature = "write")]
+use std::ops::Deref;
+
+/// A read-only client for V-Archive.
+///
+/// It has only loading functions. For registering records, make `AuthClient` with
+/// `Client::authenticate()` (needs `write` feature).
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::Client;
+/// #
+/// # fn main() {
+/// let client = Client::new();
+/// let tier_record = client.load_user_tier("내꺼", 6).unwrap();
+///
+/// println!("{}", tier_record.current_tier);
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Client {}
+
+impl Client {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Same as `load_user_tier()`
+    pub fn load_user_tier(
+        &self,
+        username: &str,
+        buttons: u8,
+    ) -> Result<UserTierRecordTable, APIError> {
+        crate::load_user_tier(username, buttons)
+    }
+
+    /// Same as `load_user_floor_board()`
+    pub fn load_user_floor_board(
+        &self,
+        username: &str,
+        buttons: u8,
+        board_type: &str,
+    ) -> Result<UserFloorRecordBoard, APIError> {
+        crate::load_user_floor_board(username, buttons, board_type)
+    }
+
+    /// Same as `load_user_song_result()`
+    pub fn load_user_song_result(
+        &self,
+        username: &str,
+        song_id: usize,
+    ) -> Result<SongUserRecord, APIError> {
+        crate::load_user_song_result(username, song_id)
+    }
+
+    /// Same as `all_songs()`
+    pub fn all_songs(&self) -> Result<Vec<SongSet>, APIError> {
+        crate::all_songs()
+    }
+
+    /// Same as `Catalog::load()`
+    pub fn catalog(&self) -> Result<Catalog, APIError> {
+        Catalog::load()
+    }
+
+    /// Same as `all_dlcs()`
+    pub fn all_dlcs(&self) -> Result<Vec<Dlc>, APIError> {
+        crate::all_dlcs()
+    }
+
+    /// Same as `tier_list()`
+    pub fn tier_list(&self) -> Result<Vec<Tier>, APIError> {
+        crate::tier_list()
+    }
+
+    /// Same as `board_types()`
+    pub fn board_types(&self) -> Result<Vec<String>, APIError> {
+        crate::board_types()
+    }
+
+    /// Attach a user's token, for writing functions
+    #[cfg(feature = "write")]
+    pub fn authenticate(self, token: UserToken) -> AuthClient {
+        AuthClient {
+            client: self,
+            token,
+            journal: None,
+            aliases: None,
+        }
+    }
+}
+
+/// A client with a user's token. It has writing functions, and every loading function of `Client`.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{Client, UserChartRecord, UserToken};
+/// #
+/// # fn example(record: UserChartRecord) {
+/// let token = UserToken::from_env().unwrap();
+/// let client = Client::new().authenticate(token);
+///
+/// let (result, _) = client.register_record(&record);
+/// println!("Updated: {}", result.unwrap().update);
+/// # }
+/// ```
+#[cfg(feature = "write")]
+#[derive(Debug)]
+pub struct AuthClient {
+    client: Client,
+    token: UserToken,
+    journal: Option<Journal>,
+    aliases: Option<AliasTable>,
+}
+
+#[cfg(feature = "write")]
+impl AuthClient {
+    pub fn token(&self) -> &UserToken {
+        &self.token
+    }
+
+    /// Write every registration try of this client to the journal
+    pub fn set_journal(&mut self, journal: Journal) {
+        self.journal = Some(journal);
+    }
+
+    /// Change a title which is an alias to the real title, before registering a record
+    pub fn set_aliases(&mut self, aliases: AliasTable) {
+        self.aliases = Some(aliases);
+    }
+
+    /// Detach the token, and go back to a read-only client
+    pub fn into_read_only(self) -> Client {
+        self.client
+    }
+
+    /// Register a user's record for a chart.
+    ///
+    /// If the record has problems (e. g. no `song_cat`), it returns `APIError::WrongParameter`.
+    /// If the journal cannot be written, the record is not sent (`APIError::JournalError`).
+    /// An error on writing the journal after sending is given with the result, because the
+    /// record was sent anyway.
+    pub fn register_record(
+        &self,
+        record: &UserChartRecord,
+    ) -> (Result<APIRegisterResult, APIError>, Option<io::Error>) {
+        register_record(
+            &self.token,
+            record,
+            self.journal.as_ref(),
+            self.aliases.as_ref(),
+        )
+    }
+
+    /// Register a bunch of user's records.
+    ///
+    /// Every record is checked before sending anything (also with `validate_record()` if
+    /// `options.songs` is given), and records are sent with `options.interval` between each request.
+    /// With `options.safe_username`, records are checked with current ones on server like
+    /// `safe_register_record()`. It returns `Err` only if the DLC list cannot be loaded.
+    /// If `options.journal` (or `options.aliases`) is `None`, the client's one is used.
+    /// ## Example
+    /// ```no_run
+    /// # use v_archive_rs::{AuthClient, RegisterOptions, UserChartRecord};
+    /// #
+    /// # fn example(client: AuthClient, records: Vec<UserChartRecord>) {
+    /// let songs = client.all_songs().unwrap();
+    /// let mut options = RegisterOptions::new();
+    /// options.songs = Some(&songs);
+    ///
+    /// let report = client.register_records(records, &options).unwrap();
+    ///
+    /// for entry in report.entries {
+    ///     println!("{}: {:?}", entry.record.title, entry.outcome);
+    /// }
+    /// # }
+    /// ```
+    pub fn register_records<I>(
+        &self,
+        records: I,
+        options: &RegisterOptions,
+    ) -> Result<RegisterReport, APIError>
+    where
+        I: IntoIterator<Item = UserChartRecord>,
+    {
+        let mut options = options.clone();
+        if options.journal.is_none() {
+            options.journal = self.journal.as_ref();
+        }
+        if options.aliases.is_none() {
+            options.aliases = self.aliases.as_ref();
+        }
+        register_records(&self.token, records, &options)
+    }
+
+    /// Register a user's record only if it improves one on server.
+    ///
+    /// It loads the current record with `load_user_song_result()` first, and skips (with the reason)
+    /// a record with lower accuracy rate, losing MAX COMBO, or nothing new.
+    /// `record.song_id` should be set.
+    /// ## Example
+    /// ```no_run
+    /// # use v_archive_rs::{AuthClient, SafeRegisterOutcome, UserChartRecord};
+    /// #
+    /// # fn example(client: AuthClient, record: UserChartRecord) {
+    /// let result = client.safe_register_record("내꺼", &record).unwrap();
+    ///
+    /// match result.outcome {
+    ///     SafeRegisterOutcome::Registered(r) => println!("Registered: {:?}", r),
+    ///     SafeRegisterOutcome::Skipped(reason) => println!("Skipped: {}", reason),
+    /// }
+    /// # }
+    /// ```
+    pub fn safe_register_record(
+        &self,
+        username: &str,
+        record: &UserChartRecord,
+    ) -> Result<SafeRegisterResult, APIError> {
+        safe_register_record(
+            &self.token,
+            username,
+            record,
+            self.journal.as_ref(),
+            self.aliases.as_ref(),
+        )
+    }
+
+    pub(crate) fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    /// Send a checked record (for `SubmissionQueue::flush()`).
+    /// If `journal` is `None`, the client's journal is used.
+    pub(crate) fn send_checked_record(
+        &self,
+        record: &UserChartRecord,
+        dlc_name: &str,
+        journal: Option<&Journal>,
+    ) -> io::Result<SentRecord> {
+        send_record(
+            &self.token,
+            record,
+            dlc_name,
+            journal.or(self.journal.as_ref()),
+        )
+    }
+}
+
+#[cfg(feature = "write")]
+impl Deref for AuthClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
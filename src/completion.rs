@@ -0,0 +1,194 @@
+use crate::{
+    ButtonMode, ChartRef, ChartType, ClearLamp, Dlc, LampCounts, SongCatagory, SongSet,
+    UserChartRecord,
+};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+/// Lamps for each `(button mode, chart type)` on a pack
+type PackCells = BTreeMap<(ButtonMode, ChartType), LampCounts>;
+
+/// Lamps of charts for a button mode and a chart type on a pack
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionCell {
+    pub button: ButtonMode,
+    pub chart_type: ChartType,
+    /// Lamps of all charts on the pack. A chart without a record is `ClearLamp::Unplayed`.
+    pub lamps: LampCounts,
+}
+
+impl CompletionCell {
+    pub fn total(&self) -> usize {
+        self.lamps.total()
+    }
+
+    /// Rate of charts with the lamp or better (`0.0` to `1.0`)
+    pub fn rate(&self, lamp: ClearLamp) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.lamps.at_least(lamp) as f64 / total as f64,
+        }
+    }
+}
+
+/// Completion of a DLC pack (a song category)
+#[derive(Debug, Clone)]
+pub struct PackCompletion {
+    pub category: SongCatagory,
+    /// A DLC for the category on `dlcs.json` (`None` if it's not there)
+    pub dlc: Option<Dlc>,
+    /// Numbers of songs on the pack
+    pub songs: usize,
+    /// Cells in order of button mode and chart type
+    pub cells: Vec<CompletionCell>,
+}
+
+impl PackCompletion {
+    pub fn cell(&self, button: &ButtonMode, chart_type: &ChartType) -> Option<&CompletionCell> {
+        self.cells
+            .iter()
+            .find(|c| &c.button == button && &c.chart_type == chart_type)
+    }
+
+    /// Lamps of all charts on the pack
+    pub fn lamps(&self) -> LampCounts {
+        let mut lamps = LampCounts::new();
+        for cell in &self.cells {
+            lamps.merge(&cell.lamps);
+        }
+        lamps
+    }
+}
+
+/// Completion of each DLC pack, by joining a song list (or `Catalog`) with a user's records.
+///
+/// Packs are sorted by release date on DLCs (from `all_dlcs()`); packs not on DLCs go last.
+/// If there are several records for a chart, the best lamp is used.
+/// For only packs a player owns, give `catalog.restrict(&owned)` as songs.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{all_dlcs, dlc_completion, load_user_floor_board, ButtonMode, Catalog, ChartType, ClearLamp};
+/// #
+/// # fn main() {
+/// let catalog = Catalog::load().unwrap();
+/// let dlcs = all_dlcs().unwrap();
+/// let board = load_user_floor_board("내꺼", 6, "SC").unwrap();
+/// let records = board.floors.iter().flat_map(|f| f.records.iter());
+///
+/// for pack in dlc_completion(&catalog, &dlcs, records) {
+///     if let Some(cell) = pack.cell(&ButtonMode::Six, &ChartType::Sc) {
+///         println!("{}: {:.1}% max combo", pack.category, cell.rate(ClearLamp::MaxCombo) * 100.0);
+///     }
+/// }
+/// # }
+/// ```
+pub fn dlc_completion<'a, I>(songs: &[SongSet], dlcs: &[Dlc], records: I) -> Vec<PackCompletion>
+where
+    I: IntoIterator<Item = &'a UserChartRecord>,
+{
+    let mut best: HashMap<ChartRef, ClearLamp> = HashMap::new();
+    for r in records {
+        let lamp = best.entry(r.chart_ref()).or_insert(ClearLamp::Unplayed);
+        *lamp = (*lamp).max(r.clear_lamp());
+    }
+
+    let mut packs: Vec<(SongCatagory, usize, PackCells)> = Vec::new();
+    for song in songs {
+        let category = &song.content.song_cat;
+        let index = match packs.iter().position(|(c, _, _)| c == category) {
+            Some(i) => i,
+            None => {
+                packs.push((category.clone(), 0, BTreeMap::new()));
+                packs.len() - 1
+            }
+        };
+        let (_, song_count, cells) = &mut packs[index];
+        *song_count += 1;
+
+        for chart in &song.charts {
+            let lamp = best
+                .get(&ChartRef::of(&song.content, chart))
+                .copied()
+                .unwrap_or(ClearLamp::Unplayed);
+            cells
+                .entry((chart.button.clone(), chart.chart_type.clone()))
+                .or_default()
+                .add(lamp);
+        }
+    }
+
+    let mut completions: Vec<PackCompletion> = packs
+        .into_iter()
+        .map(|(category, songs, cells)| {
+            let code = category.to_string();
+            PackCompletion {
+                dlc: dlcs.iter().find(|d| d.dlc_code == code).cloned(),
+                category,
+                songs,
+                cells: cells
+                    .into_iter()
+                    .map(|((button, chart_type), lamps)| CompletionCell {
+                        button,
+                        chart_type,
+                        lamps,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    completions.sort_by(|a, b| match (&a.dlc, &b.dlc) {
+        (Some(x), Some(y)) => x.release_date.cmp(&y.release_date),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.category.to_string().cmp(&b.category.to_string()),
+    });
+
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::all_dlcs_parse;
+    use crate::test_util::{record, songs};
+    const DLCS_JSON: &str = r#"[
+        {"dlcCode": "VE3", "dlcName": "V EXTENSION III", "ymdt": "2022-03-10"},
+        {"dlcCode": "R", "dlcName": "RESPECT", "ymdt": "2017-07-28"}
+    ]"#;
+
+    #[test]
+    fn completion_by_pack() {
+        let songs = songs();
+        let dlcs = all_dlcs_parse(DLCS_JSON.to_string());
+        let records = vec![
+            record(11, ChartType::Sc).with_acc(98.0),
+            record(11, ChartType::Sc)
+                .with_acc(99.0)
+                .with_max_combo(true),
+            record(12, ChartType::Sc)
+                .with_acc(100.0)
+                .with_max_combo(true),
+            record(11, ChartType::Normal).with_acc(99.0),
+        ];
+
+        let packs = dlc_completion(&songs, &dlcs, &records);
+        let codes: Vec<String> = packs.iter().map(|p| p.category.to_string()).collect();
+        assert_eq!(codes, vec!["R", "VE3", "CY", "ES", "GG", "P1", "P3", "VE"]);
+
+        let pack = &packs[0];
+        assert_eq!(pack.songs, 8);
+        assert_eq!(pack.dlc.as_ref().unwrap().dlc_name, "RESPECT");
+
+        let sc = pack.cell(&ButtonMode::Six, &ChartType::Sc).unwrap();
+        assert_eq!(sc.total(), 6);
+        assert_eq!(sc.lamps.max_combo, 1);
+        assert_eq!(sc.lamps.perfect, 1);
+        assert_eq!(sc.rate(ClearLamp::MaxCombo), 2.0 / 6.0);
+
+        let nm = pack.cell(&ButtonMode::Six, &ChartType::Normal).unwrap();
+        assert_eq!((nm.lamps.played, nm.lamps.unplayed), (1, 1));
+        assert_eq!(pack.lamps().total(), 13);
+        assert!(pack.cell(&ButtonMode::Five, &ChartType::Normal).is_none());
+    }
+}
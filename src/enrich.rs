@@ -0,0 +1,189 @@
+use crate::{Catalog, ChartRef, Dlc, SongCatagory, SongSet, UserChartRecord};
+use std::fmt;
+
+/// Problems on enriching a record: the record is not on the catalog anymore
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnrichProblem {
+    /// No song for the song ID (or the title, if the song ID is `0`)
+    UnknownSong(usize, String),
+    /// The song is found, but not the chart
+    UnknownChart(ChartRef),
+}
+
+impl fmt::Display for EnrichProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSong(id, title) => write!(f, "Unknown song: {} (#{})", title, id),
+            Self::UnknownChart(c) => write!(f, "Unknown chart: {}", c),
+        }
+    }
+}
+
+/// A record with metadata from `Catalog` and DLCs
+#[derive(Debug)]
+pub struct EnrichedRecord {
+    pub record: UserChartRecord,
+    pub artist: Option<String>,
+    /// A DLC for the song's category
+    pub dlc: Option<Dlc>,
+    /// `Some` if the song or the chart is not on the catalog
+    pub problem: Option<EnrichProblem>,
+}
+
+impl EnrichedRecord {
+    pub fn is_known(&self) -> bool {
+        self.problem.is_none()
+    }
+}
+
+/// Fill missing fields of a record from a catalog and DLCs (from `all_dlcs()`).
+///
+/// Each loading function fills a different set of fields; this fills `title`, `song_cat`,
+/// `chart_level`, `floor_level` and `maximum_rating` if they are missing. Fields already on the
+/// record are kept. `user_rating` and `dj_power` depend on the user's play, so they are not filled.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{all_dlcs, enrich_records, load_user_floor_board, Catalog};
+/// #
+/// # fn main() {
+/// let catalog = Catalog::load().unwrap();
+/// let dlcs = all_dlcs().unwrap();
+///
+/// let board = load_user_floor_board("내꺼", 6, "SC").unwrap();
+/// let records = board.floors.into_iter().flat_map(|f| f.records);
+///
+/// for r in enrich_records(records, &catalog, &dlcs) {
+///     match r.problem {
+///         Some(p) => println!("{}", p),
+///         None => println!("{} - {:?} (Lv.{:?})", r.record.title, r.artist, r.record.chart_level),
+///     }
+/// }
+/// # }
+/// ```
+pub fn enrich_record(
+    mut record: UserChartRecord,
+    catalog: &Catalog,
+    dlcs: &[Dlc],
+) -> EnrichedRecord {
+    let song = match find_song(&record, catalog) {
+        Some(s) => s,
+        None => {
+            let problem = EnrichProblem::UnknownSong(record.song_id, record.title.clone());
+            let dlc = record.song_cat.as_ref().and_then(|c| find_dlc(dlcs, c));
+            return EnrichedRecord {
+                record,
+                artist: None,
+                dlc,
+                problem: Some(problem),
+            };
+        }
+    };
+
+    record.song_id = song.content.song_id;
+    if record.title.is_empty() {
+        record.title = song.content.title.clone();
+    }
+    if record.song_cat.is_none() {
+        record.song_cat = Some(song.content.song_cat.clone());
+    }
+
+    let chart_ref = record.chart_ref();
+    let problem = match song.chart(&chart_ref) {
+        Some(chart) => {
+            record.chart_level = record.chart_level.or(Some(chart.level));
+            record.floor_level = record.floor_level.or(chart.floor);
+            record.maximum_rating = record.maximum_rating.or(chart.rating);
+            None
+        }
+        None => Some(EnrichProblem::UnknownChart(chart_ref)),
+    };
+
+    EnrichedRecord {
+        record,
+        artist: Some(song.content.artist.clone()),
+        dlc: find_dlc(dlcs, &song.content.song_cat),
+        problem,
+    }
+}
+
+/// `enrich_record()` for several records
+pub fn enrich_records<I>(records: I, catalog: &Catalog, dlcs: &[Dlc]) -> Vec<EnrichedRecord>
+where
+    I: IntoIterator<Item = UserChartRecord>,
+{
+    records
+        .into_iter()
+        .map(|r| enrich_record(r, catalog, dlcs))
+        .collect()
+}
+
+/// By song ID, or by title only if the song ID is unknown (`0`)
+fn find_song<'a>(record: &UserChartRecord, catalog: &'a Catalog) -> Option<&'a SongSet> {
+    if record.song_id != 0 {
+        return catalog.song(record.song_id);
+    }
+
+    match catalog.songs_by_title(&record.title).as_slice() {
+        [song] => Some(*song),
+        _ => None,
+    }
+}
+
+fn find_dlc(dlcs: &[Dlc], category: &SongCatagory) -> Option<Dlc> {
+    let code = category.to_string();
+    dlcs.iter().find(|d| d.dlc_code == code).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{record, songs};
+    use crate::{all_dlcs_parse, ChartType};
+
+    const DLCS_JSON: &str = r#"[
+        {"dlcCode": "VE3", "dlcName": "V EXTENSION III", "ymdt": "2022-03-10"}
+    ]"#;
+
+    fn played(song_id: usize, title: &str, chart_type: ChartType) -> UserChartRecord {
+        record(song_id, chart_type)
+            .with_title(title)
+            .with_acc(99.5)
+            .with_floor(14.3)
+    }
+
+    #[test]
+    fn enrich_from_catalog() {
+        let catalog = Catalog::new(songs());
+        let dlcs = all_dlcs_parse(DLCS_JSON.to_string());
+
+        let records = vec![
+            played(555, "", ChartType::Sc),
+            played(0, "Gloxinia", ChartType::Sc),
+            played(555, "Gloxinia", ChartType::Hard),
+            played(999, "Removed", ChartType::Sc),
+        ];
+        let enriched = enrich_records(records, &catalog, &dlcs);
+
+        let first = &enriched[0];
+        assert!(first.is_known());
+        assert_eq!(first.record.title, "Gloxinia");
+        assert_eq!(first.record.chart_level, Some(14));
+        assert_eq!(first.record.maximum_rating, Some(210.5));
+        // Kept from the record
+        assert_eq!(first.record.floor_level, Some(14.3));
+        assert_eq!(first.record.song_cat, Some(SongCatagory::from("VE3")));
+        assert_eq!(first.artist.as_deref(), Some("ESTi"));
+        assert_eq!(first.dlc.as_ref().unwrap().dlc_name, "V EXTENSION III");
+
+        assert_eq!(enriched[1].record.song_id, 555);
+        assert!(matches!(
+            enriched[2].problem,
+            Some(EnrichProblem::UnknownChart(_))
+        ));
+        assert_eq!(enriched[2].artist.as_deref(), Some("ESTi"));
+        assert!(matches!(
+            enriched[3].problem,
+            Some(EnrichProblem::UnknownSong(999, _))
+        ));
+    }
+}
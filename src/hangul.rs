@@ -0,0 +1,132 @@
+//! Helpers for Hangul: initial consonants (chosung) and Revised Romanization of Korean
+
+const SYLLABLE_FIRST: u32 = 0xAC00;
+const SYLLABLE_LAST: u32 = 0xD7A3;
+const MEDIAL_COUNT: u32 = 21;
+const FINAL_COUNT: u32 = 28;
+
+/// Initial consonants, as compatibility jamo
+const INITIAL_JAMO: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ',
+    'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+const INITIAL_ROMAN: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p",
+    "h",
+];
+
+const MEDIAL_ROMAN: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we",
+    "wi", "yu", "eu", "ui", "i",
+];
+
+/// Final consonants at the end of a word (or before a consonant)
+const FINAL_ROMAN: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p", "p",
+    "t", "t", "ng", "t", "t", "k", "t", "p", "t",
+];
+
+/// Final consonants followed by a vowel (moved to the next syllable)
+const FINAL_ROMAN_LIAISON: [&str; 28] = [
+    "", "g", "kk", "ks", "n", "nj", "nh", "d", "r", "lg", "lm", "lb", "ls", "lt", "lp", "lh", "m",
+    "b", "ps", "s", "ss", "ng", "j", "ch", "k", "t", "p", "",
+];
+
+/// Split a Hangul syllable to `(initial, medial, final)` indexes
+fn decompose(c: char) -> Option<(usize, usize, usize)> {
+    let code = c as u32;
+    if !(SYLLABLE_FIRST..=SYLLABLE_LAST).contains(&code) {
+        return None;
+    }
+
+    let index = code - SYLLABLE_FIRST;
+    let initial = index / (MEDIAL_COUNT * FINAL_COUNT);
+    let medial = (index % (MEDIAL_COUNT * FINAL_COUNT)) / FINAL_COUNT;
+    let last = index % FINAL_COUNT;
+
+    Some((initial as usize, medial as usize, last as usize))
+}
+
+pub(crate) fn is_syllable(c: char) -> bool {
+    decompose(c).is_some()
+}
+
+/// Whether the character is a consonant jamo (e. g. `ㄴ`)
+pub(crate) fn is_consonant_jamo(c: char) -> bool {
+    INITIAL_JAMO.contains(&c)
+}
+
+/// An initial consonant of a syllable (e. g. `꿈` -> `ㄲ`)
+pub(crate) fn chosung(c: char) -> Option<char> {
+    decompose(c).map(|(i, _, _)| INITIAL_JAMO[i])
+}
+
+/// Whether a query character matches a text character.
+/// A consonant jamo on the query matches any syllable starts with it.
+pub(crate) fn jamo_matches(query: char, text: char) -> bool {
+    query == text || (is_consonant_jamo(query) && chosung(text) == Some(query))
+}
+
+/// Romanize Hangul syllables by Revised Romanization of Korean. Other characters are kept.
+///
+/// It only follows simple rules (e. g. liaison of a final consonant); it's for searching, not
+/// for displaying.
+pub(crate) fn romanize(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut romanized = String::new();
+
+    for (index, c) in chars.iter().enumerate() {
+        let (initial, medial, last) = match decompose(*c) {
+            Some(d) => d,
+            None => {
+                romanized.push(*c);
+                continue;
+            }
+        };
+
+        let previous = index.checked_sub(1).and_then(|i| decompose(chars[i]));
+        let next = chars.get(index + 1).and_then(|n| decompose(*n));
+
+        // A silent `ㅇ` after a final consonant: the final is already written
+        let previous_moved = matches!(previous, Some((_, _, l)) if l != 0 && l != 21);
+        if !(initial == 11 && previous_moved) {
+            // `ㄹ` after a final `ㄹ` is `l`
+            if initial == 5 && matches!(previous, Some((_, _, 8))) {
+                romanized.push('l');
+            } else {
+                romanized.push_str(INITIAL_ROMAN[initial]);
+            }
+        }
+
+        romanized.push_str(MEDIAL_ROMAN[medial]);
+
+        let is_before_vowel = matches!(next, Some((11, _, _)));
+        if is_before_vowel && last != 21 {
+            romanized.push_str(FINAL_ROMAN_LIAISON[last]);
+        } else {
+            romanized.push_str(FINAL_ROMAN[last]);
+        }
+    }
+
+    romanized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hangul_helpers() {
+        assert_eq!(chosung('꿈'), Some('ㄲ'));
+        assert!(jamo_matches('ㅂ', '비'));
+        assert!(!jamo_matches('ㅅ', '비'));
+
+        assert_eq!(romanize("비상"), "bisang");
+        assert_eq!(romanize("고백"), "gobaek");
+        assert_eq!(romanize("음악"), "eumak");
+        assert_eq!(romanize("별빛"), "byeolbit");
+        assert_eq!(romanize("설레"), "seolle");
+        assert_eq!(romanize("비상 ~Stay~"), "bisang ~Stay~");
+    }
+}
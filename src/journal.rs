@@ -0,0 +1,195 @@
+use crate::APIRegisterResult;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// A line on `Journal`, for a registration try
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry {
+    /// When the request is sent
+    pub timestamp: DateTime<Utc>,
+    /// A user number of the token (the token itself is never written)
+    pub user_num: usize,
+    /// The request body as sent to server
+    pub payload: serde_json::Value,
+    /// HTTP status code (`None` if the server is not reached)
+    pub status: Option<u16>,
+    /// A parsed response on success
+    pub result: Option<APIRegisterResult>,
+    /// An error on failure
+    pub error: Option<String>,
+}
+
+impl JournalEntry {
+    /// A song title on the payload
+    pub fn title(&self) -> Option<&str> {
+        self.payload.get("name").and_then(|n| n.as_str())
+    }
+}
+
+/// Conditions for `Journal::query()`. `None` means "any".
+#[derive(Debug, Clone, Default)]
+pub struct JournalQuery {
+    pub user_num: Option<usize>,
+    /// A song title (same as sent)
+    pub title: Option<String>,
+    /// Entries at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Entries before this time
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl JournalQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn matches(&self, entry: &JournalEntry) -> bool {
+        self.user_num.is_none_or(|u| u == entry.user_num)
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|t| entry.title() == Some(t.as_str()))
+            && self.since.is_none_or(|t| entry.timestamp >= t)
+            && self.until.is_none_or(|t| entry.timestamp < t)
+    }
+}
+
+/// An append-only journal (JSON lines file) for every registration try.
+///
+/// It's opt-in: give it to `RegisterOptions.journal` or `SubmissionQueue::set_journal()`.
+///
+/// Only requests sent to server are written. Records rejected by checking before sending
+/// (e. g. no `song_cat`) are not sent, so they are not on the journal.
+/// If the journal cannot be written, the record is not sent; if writing fails after sending,
+/// the error is given with the result (`RegisterEntry.journal_error`, or `APIError::JournalError`).
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{Journal, JournalQuery};
+/// #
+/// # fn main() {
+/// let journal = Journal::open("register_journal.jsonl");
+///
+/// let mut query = JournalQuery::new();
+/// query.user_num = Some(1);
+/// query.title = Some("Gloxinia".to_string());
+///
+/// for entry in journal.query(&query).unwrap() {
+///     println!("{} {:?} {:?}", entry.timestamp, entry.status, entry.result);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Use a journal file. The file is made on the first write.
+    pub fn open<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check the journal file can be written (it's made if it doesn't exist)
+    #[cfg(feature = "write")]
+    pub(crate) fn check_writable(&self) -> io::Result<()> {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map(|_| ())
+    }
+
+    #[cfg(feature = "write")]
+    pub(crate) fn append(&self, entry: &JournalEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        line.push('\n');
+
+        use std::io::Write;
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())
+    }
+
+    /// All entries on the journal, in written order
+    pub fn entries(&self) -> io::Result<Vec<JournalEntry>> {
+        self.query(&JournalQuery::new())
+    }
+
+    /// Entries matching the query, in written order
+    pub fn query(&self, query: &JournalQuery) -> io::Result<Vec<JournalEntry>> {
+        let file = match fs::File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: JournalEntry = serde_json::from_str(&line)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if query.matches(&entry) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn journal_write_and_query() {
+        let path =
+            std::env::temp_dir().join(format!("v_archive_rs_journal_{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let journal = Journal::open(&path);
+
+        for (user_num, title) in [(1, "Gloxinia"), (2, "Gloxinia"), (1, "Urban Night")] {
+            journal
+                .append(&JournalEntry {
+                    timestamp: Utc::now(),
+                    user_num,
+                    payload: serde_json::json!({ "name": title, "score": 99.1 }),
+                    status: Some(200),
+                    result: Some(APIRegisterResult {
+                        success: true,
+                        update: false,
+                    }),
+                    error: None,
+                })
+                .unwrap();
+        }
+
+        let mut query = JournalQuery::new();
+        query.user_num = Some(1);
+        assert_eq!(journal.query(&query).unwrap().len(), 2);
+
+        query.title = Some("Gloxinia".to_string());
+        assert_eq!(journal.query(&query).unwrap().len(), 1);
+
+        query.since = Some(Utc::now() + chrono::Duration::hours(1));
+        assert!(journal.query(&query).unwrap().is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
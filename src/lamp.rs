@@ -0,0 +1,246 @@
+use crate::{SongCatagory, UserChartRecord, UserFloorRecordBoard, UserFloorRecordSet};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// A clear state of a record. Better states are greater (`Unplayed < ... < Perfect`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ClearLamp {
+    Unplayed,
+    Played,
+    MaxCombo,
+    /// 100% accuracy
+    Perfect,
+}
+
+impl ClearLamp {
+    pub fn of(record: &UserChartRecord) -> Self {
+        match record.acc_rate {
+            None => Self::Unplayed,
+            Some(acc) if acc >= 100.0 => Self::Perfect,
+            Some(_) if record.is_max_combo => Self::MaxCombo,
+            Some(_) => Self::Played,
+        }
+    }
+
+    /// All lamps, in order
+    pub fn all() -> [Self; 4] {
+        [Self::Unplayed, Self::Played, Self::MaxCombo, Self::Perfect]
+    }
+
+    /// A lamp from a name (e. g. `unplayed`, `played`, `maxcombo`/`mc`, `perfect`/`pp`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "unplayed" | "none" => Some(Self::Unplayed),
+            "played" | "clear" => Some(Self::Played),
+            "maxcombo" | "max_combo" | "mc" => Some(Self::MaxCombo),
+            "perfect" | "pp" => Some(Self::Perfect),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ClearLamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unplayed => write!(f, "UNPLAYED"),
+            Self::Played => write!(f, "PLAYED"),
+            Self::MaxCombo => write!(f, "MAX COMBO"),
+            Self::Perfect => write!(f, "PERFECT"),
+        }
+    }
+}
+
+impl UserChartRecord {
+    pub fn clear_lamp(&self) -> ClearLamp {
+        ClearLamp::of(self)
+    }
+}
+
+/// Numbers of records for each lamp
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LampCounts {
+    pub unplayed: usize,
+    pub played: usize,
+    pub max_combo: usize,
+    pub perfect: usize,
+}
+
+impl LampCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn of<'a, I: IntoIterator<Item = &'a UserChartRecord>>(records: I) -> Self {
+        let mut counts = Self::new();
+        for r in records {
+            counts.add(r.clear_lamp());
+        }
+        counts
+    }
+
+    pub fn add(&mut self, lamp: ClearLamp) {
+        match lamp {
+            ClearLamp::Unplayed => self.unplayed += 1,
+            ClearLamp::Played => self.played += 1,
+            ClearLamp::MaxCombo => self.max_combo += 1,
+            ClearLamp::Perfect => self.perfect += 1,
+        }
+    }
+
+    /// Add counts of another
+    pub fn merge(&mut self, other: &LampCounts) {
+        self.unplayed += other.unplayed;
+        self.played += other.played;
+        self.max_combo += other.max_combo;
+        self.perfect += other.perfect;
+    }
+
+    /// Records with exactly the lamp
+    pub fn count(&self, lamp: ClearLamp) -> usize {
+        match lamp {
+            ClearLamp::Unplayed => self.unplayed,
+            ClearLamp::Played => self.played,
+            ClearLamp::MaxCombo => self.max_combo,
+            ClearLamp::Perfect => self.perfect,
+        }
+    }
+
+    /// Records with the lamp or better (e. g. `MaxCombo` counts perfect plays too)
+    pub fn at_least(&self, lamp: ClearLamp) -> usize {
+        ClearLamp::all()
+            .into_iter()
+            .filter(|l| *l >= lamp)
+            .map(|l| self.count(l))
+            .sum()
+    }
+
+    pub fn total(&self) -> usize {
+        self.at_least(ClearLamp::Unplayed)
+    }
+}
+
+/// Lamps for each chart level. Records without `chart_level` are skipped
+/// (fill it with `enrich_records()`).
+pub fn lamps_by_level<'a, I>(records: I) -> BTreeMap<u8, LampCounts>
+where
+    I: IntoIterator<Item = &'a UserChartRecord>,
+{
+    let mut lamps: BTreeMap<u8, LampCounts> = BTreeMap::new();
+    for r in records {
+        if let Some(level) = r.chart_level {
+            lamps.entry(level).or_default().add(r.clear_lamp());
+        }
+    }
+    lamps
+}
+
+/// Lamps for each floor, in order of floor. Records without `floor_level` are skipped.
+pub fn lamps_by_floor<'a, I>(records: I) -> Vec<(f64, LampCounts)>
+where
+    I: IntoIterator<Item = &'a UserChartRecord>,
+{
+    let mut floors: Vec<(f64, ClearLamp)> = records
+        .into_iter()
+        .filter_map(|r| r.floor_level.map(|f| (f, r.clear_lamp())))
+        .collect();
+    floors.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut lamps: Vec<(f64, LampCounts)> = Vec::new();
+    for (floor, lamp) in floors {
+        match lamps.last_mut() {
+            Some((f, counts)) if *f == floor => counts.add(lamp),
+            _ => {
+                let mut counts = LampCounts::new();
+                counts.add(lamp);
+                lamps.push((floor, counts));
+            }
+        }
+    }
+    lamps
+}
+
+/// Lamps for each song category (DLC). Records without `song_cat` are skipped.
+pub fn lamps_by_dlc<'a, I>(records: I) -> HashMap<SongCatagory, LampCounts>
+where
+    I: IntoIterator<Item = &'a UserChartRecord>,
+{
+    let mut lamps: HashMap<SongCatagory, LampCounts> = HashMap::new();
+    for r in records {
+        if let Some(category) = &r.song_cat {
+            lamps
+                .entry(category.clone())
+                .or_default()
+                .add(r.clear_lamp());
+        }
+    }
+    lamps
+}
+
+impl UserFloorRecordSet {
+    pub fn lamps(&self) -> LampCounts {
+        LampCounts::of(&self.records)
+    }
+}
+
+impl UserFloorRecordBoard {
+    /// Lamps of all records on the board
+    pub fn lamps(&self) -> LampCounts {
+        LampCounts::of(self.floors.iter().flat_map(|f| f.records.iter()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use crate::ChartType;
+
+    fn record(acc: Option<f64>, max_combo: bool, level: u8, floor: f64) -> UserChartRecord {
+        test_util::record(0, ChartType::Sc)
+            .with_acc(acc)
+            .with_max_combo(max_combo)
+            .with_level(level)
+            .with_floor(floor)
+            .with_category(if level > 13 { "VE" } else { "R" })
+    }
+
+    #[test]
+    fn lamps_and_counts() {
+        let records = vec![
+            record(None, false, 13, 13.2),
+            record(Some(98.0), false, 13, 13.2),
+            record(Some(99.5), true, 14, 14.1),
+            record(Some(100.0), true, 14, 14.5),
+        ];
+
+        let lamps: Vec<ClearLamp> = records.iter().map(|r| r.clear_lamp()).collect();
+        assert_eq!(lamps, ClearLamp::all().to_vec());
+        assert!(ClearLamp::Perfect > ClearLamp::MaxCombo);
+
+        let counts = LampCounts::of(&records);
+        assert_eq!(counts.total(), 4);
+        assert_eq!(counts.at_least(ClearLamp::MaxCombo), 2);
+
+        let by_level = lamps_by_level(&records);
+        assert_eq!(by_level[&13].unplayed, 1);
+        assert_eq!(by_level[&14].at_least(ClearLamp::MaxCombo), 2);
+
+        let by_floor = lamps_by_floor(&records);
+        assert_eq!(by_floor.len(), 3);
+        assert_eq!(
+            by_floor[0],
+            (
+                13.2,
+                LampCounts {
+                    unplayed: 1,
+                    played: 1,
+                    max_combo: 0,
+                    perfect: 0
+                }
+            )
+        );
+
+        let by_dlc = lamps_by_dlc(&records);
+        assert_eq!(by_dlc[&SongCatagory::from("VE")].perfect, 1);
+    }
+}
@@ -7,6 +7,68 @@ use std::num::ParseIntError;
 use std::str::FromStr;
 use ureq::{Error, Response};
 
+mod alias;
+mod breakdown;
+mod catalog;
+mod chart_ref;
+mod client;
+mod completion;
+mod enrich;
+mod hangul;
+mod journal;
+mod lamp;
+mod milestone;
+mod owned;
+mod predict;
+mod query;
+#[cfg(feature = "write")]
+mod queue;
+mod recommend;
+#[cfg(feature = "write")]
+mod register;
+mod search;
+mod skill;
+mod stats;
+#[cfg(test)]
+mod test_util;
+mod token;
+mod validate;
+mod versus;
+
+pub use alias::{AliasConflict, AliasError, AliasTable};
+pub use breakdown::{DisplaceCandidate, RecordContribution, TierBreakdown};
+pub use catalog::Catalog;
+pub use chart_ref::{ChartRef, ChartRefError};
+#[cfg(feature = "write")]
+pub use client::AuthClient;
+pub use client::Client;
+pub use completion::{dlc_completion, CompletionCell, PackCompletion};
+pub use enrich::{enrich_record, enrich_records, EnrichProblem, EnrichedRecord};
+pub use journal::{Journal, JournalEntry, JournalQuery};
+pub use lamp::{lamps_by_dlc, lamps_by_floor, lamps_by_level, ClearLamp, LampCounts};
+pub use milestone::{MilestoneCell, MilestoneKey, MilestoneMatrix, MilestoneRow, Milestones};
+pub use owned::{OwnedContent, OwnedContentError};
+pub use predict::{AccuracyModel, Prediction, Residual};
+pub use query::{
+    ChartQuery, Comparison, QueryError, QueryErrorKind, QueryField, SortKey, SortOrder,
+};
+#[cfg(feature = "write")]
+pub use queue::{QueueError, QueueReport, QueueStatus, QueuedRecord, SubmissionQueue};
+pub use recommend::{RecommendKind, RecommendWeights, Recommendation, Recommender};
+#[cfg(feature = "write")]
+pub use register::{
+    RegisterEntry, RegisterOptions, RegisterOutcome, RegisterReport, SafeRegisterOutcome,
+    SafeRegisterResult,
+};
+pub use search::{
+    resolve_song, resolve_song_with_aliases, SearchFilter, SearchHit, SongIndex, SongResolution,
+};
+pub use skill::{FloorEvidence, SkillCriterion, SkillFloor};
+pub use stats::{BoardStats, CountMismatch, RecordStats};
+pub use token::{TokenError, TokenProfiles, UserToken, USER_NUM_ENV, USER_TOKEN_ENV};
+pub use validate::{validate_record, validate_record_with_aliases, RecordProblem};
+pub use versus::{head_to_head, ChartComparison, HeadToHead, Outcome, PlayerRecords, VersusFilter};
+
 /// An API raw struct for tier
 #[derive(Deserialize)]
 struct RawAPITier {
@@ -29,6 +91,8 @@ pub enum APIError {
     InernalServerError,
     APIUnknownError(u16, String),
     HTTPErr(u16),
+    /// Failed to write a journal (`Journal`) for registering
+    JournalError(String),
     UnknownError,
 }
 
@@ -44,6 +108,7 @@ impl fmt::Display for APIError {
             Self::InernalServerError => write!(f, "Inernal server error"),
             Self::APIUnknownError(c, m) => write!(f, "Unknown API error: {}, {}", c, m),
             Self::HTTPErr(c) => write!(f, "HTTP error: {}", c),
+            Self::JournalError(m) => write!(f, "Journal error: {}", m),
             Self::UnknownError => write!(f, "Unknown"),
         }
     }
@@ -51,6 +116,13 @@ impl fmt::Display for APIError {
 
 /// Return to error object. for in this crate
 fn catch_server_err(code: u16, resp: Response) -> APIError {
+    let resp_str = resp.into_string().unwrap_or_default();
+    server_err_of(code, &resp_str)
+}
+
+/// Return to error object from a status code and a response body.
+/// A body not in JSON (e. g. an HTML page from a proxy) is `APIError::HTTPErr`.
+fn server_err_of(code: u16, resp_str: &str) -> APIError {
     #[derive(Deserialize)]
     #[serde(rename_all = "camelCase")]
     struct APIBody {
@@ -58,11 +130,12 @@ fn catch_server_err(code: u16, resp: Response) -> APIError {
         message: String,
     }
 
-    let resp_str = resp.into_string().unwrap();
-    let error_body: APIBody = serde_json::from_str(&resp_str).unwrap();
-
     match code {
         400 | 404 => {
+            let error_body: APIBody = match serde_json::from_str(resp_str) {
+                Ok(b) => b,
+                Err(_) => return APIError::HTTPErr(code),
+            };
             let error_code = error_body.error_code;
             match error_code {
                 101 => {
@@ -96,21 +169,21 @@ fn catch_server_err(code: u16, resp: Response) -> APIError {
 }
 
 /// Cartegories for new initial contents of DJMAX RESPECT or DMRV
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RespectCat {
     Respect,
     RespectV,
 }
 
 /// Cartegories for legacy initial contents of DJMAX RESPECT or DMRV
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum LegacyCat {
     PortableOne,
     PortableTwo,
 }
 
 /// Cartegories for DLCs of legacy DJMAX series
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum LegacyExtCat {
     Trilogy,
     Clazziquai,
@@ -124,7 +197,7 @@ pub enum LegacyExtCat {
 }
 
 /// Cartegories for DLCs of new contents of DJMAX RESPECT V
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum NewExtCat {
     VExtentionOne,
     VExtentionTwo,
@@ -139,7 +212,7 @@ pub enum NewExtCat {
 }
 
 /// Cartegories for a song
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SongCatagory {
     Respect(RespectCat),
     Legacy(LegacyCat),
@@ -204,8 +277,8 @@ impl fmt::Display for SongCatagory {
                 LegacyCat::PortableTwo => write!(f, "P2"),
             },
             SongCatagory::LegacyExtention(c) => match c {
-                LegacyExtCat::Trilogy => write!(f, "P1"),
-                LegacyExtCat::Clazziquai => write!(f, "TR"),
+                LegacyExtCat::Trilogy => write!(f, "TR"),
+                LegacyExtCat::Clazziquai => write!(f, "CE"),
                 LegacyExtCat::TechnikaOne => write!(f, "T1"),
                 LegacyExtCat::BlackSquare => write!(f, "BS"),
                 LegacyExtCat::TechnikaTwo => write!(f, "T2"),
@@ -234,7 +307,7 @@ impl fmt::Display for SongCatagory {
 }
 
 /// Button modes for a chart
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum ButtonMode {
     Four,
     Five,
@@ -306,7 +379,7 @@ impl From<ButtonMode> for u8 {
 }
 
 /// Difficulty types for a chart
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum ChartType {
     Normal,
     Hard,
@@ -340,7 +413,7 @@ impl fmt::Display for ChartType {
 }
 
 /// A song's content
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Song {
     /// ID number for a song
     pub song_id: usize,
@@ -364,7 +437,7 @@ impl Song {
 }
 
 /// A chart for a song
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Chart {
     pub level: u8,
     pub floor: Option<f64>,
@@ -374,7 +447,7 @@ pub struct Chart {
 }
 
 /// A user's record for a chart
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UserChartRecord {
     /// ID number for a song of the chart
     pub song_id: usize,
@@ -457,6 +530,11 @@ impl UserChartRecord {
             None => None,
         }
     }
+
+    /// `ChartRef` for the chart of the record
+    pub fn chart_ref(&self) -> ChartRef {
+        ChartRef::from(self)
+    }
 }
 
 /// Tier enum for a table
@@ -602,6 +680,11 @@ impl UserTierRecordTable {
             top_records: Vec::new(),
         }
     }
+
+    /// A record for `ChartRef` on the top records
+    pub fn record(&self, chart: &ChartRef) -> Option<&UserChartRecord> {
+        self.top_records.iter().find(|r| chart.is_for(r))
+    }
 }
 
 fn load_user_tier_parse(parse_text: String) -> UserTierRecordTable {
@@ -737,7 +820,7 @@ impl UserFloorRecordSet {
 }
 
 /// Types of user's record floor board.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FloorBoardType {
     One,
     Two,
@@ -861,6 +944,14 @@ impl UserFloorRecordBoard {
             floors: Vec::new(),
         }
     }
+
+    /// A record for `ChartRef` on the board
+    pub fn record(&self, chart: &ChartRef) -> Option<&UserChartRecord> {
+        self.floors
+            .iter()
+            .flat_map(|f| f.records.iter())
+            .find(|r| chart.is_for(r))
+    }
 }
 
 fn user_floor_board_parse(parse_text: String) -> UserFloorRecordBoard {
@@ -991,11 +1082,19 @@ pub fn load_user_floor_board(
     }
 }
 
+#[derive(Debug)]
 pub struct SongUserRecord {
     pub song_content: Song,
     pub records: Vec<UserChartRecord>,
 }
 
+impl SongUserRecord {
+    /// A record for `ChartRef`
+    pub fn record(&self, chart: &ChartRef) -> Option<&UserChartRecord> {
+        self.records.iter().find(|r| chart.is_for(r))
+    }
+}
+
 fn user_song_result_parse(parse_text: String) -> SongUserRecord {
     #[derive(Deserialize, Default)]
     #[serde(rename_all = "camelCase")]
@@ -1561,7 +1660,18 @@ pub fn load_user_song_result(username: &str, song_id: usize) -> Result<SongUserR
     }
 }
 
-pub fn get_full_dlc_name(dlc_code: String) -> Result<String, APIError> {
+/// A DLC (or a base game pack) on V-Archive
+#[derive(Debug, Clone)]
+pub struct Dlc {
+    /// A code for the DLC (e. g.: `VE4`)
+    pub dlc_code: String,
+    /// A full name of the DLC (e. g.: `V EXTENSION IV`)
+    pub dlc_name: String,
+    /// A release date of the DLC as on *V-Archive API* (`ymdt`)
+    pub release_date: String,
+}
+
+fn all_dlcs_parse(parse_text: String) -> Vec<Dlc> {
     #[derive(Deserialize)]
     #[serde(rename_all = "camelCase")]
     struct APIObj {
@@ -1569,81 +1679,47 @@ pub fn get_full_dlc_name(dlc_code: String) -> Result<String, APIError> {
         dlc_name: String,
         ymdt: String,
     }
+
+    let list: Vec<APIObj> = serde_json::from_str(&parse_text).expect("failed to parse dlcs.json");
+
+    list.into_iter()
+        .map(|obj| Dlc {
+            dlc_code: obj.dlc_code,
+            dlc_name: obj.dlc_name,
+            release_date: obj.ymdt,
+        })
+        .collect()
+}
+
+/// Load a list of all DLCs from server
+pub fn all_dlcs() -> Result<Vec<Dlc>, APIError> {
     let resp = ureq::get("https://v-archive.net/db/dlcs.json").call();
 
     match resp {
         Ok(resp) => {
             let resp_str = resp.into_string().expect("failed to read response body");
-            let list: Vec<APIObj> =
-                serde_json::from_str(&resp_str).expect("failed to parse dlcs.json");
-
-            list.into_iter()
-                .find(|obj| obj.dlc_code == dlc_code)
-                .map(|obj| obj.dlc_name)
-                .ok_or(APIError::CannotFindSong)
+            Ok(all_dlcs_parse(resp_str))
         }
         Err(Error::Status(code, resp)) => Err(catch_server_err(code, resp)),
         Err(_) => Err(APIError::UnknownError),
     }
 }
 
-pub struct UserToken {
-    pub user_num: usize,
-    pub user_token: String,
+pub fn get_full_dlc_name(dlc_code: String) -> Result<String, APIError> {
+    all_dlcs()?
+        .into_iter()
+        .find(|dlc| dlc.dlc_code == dlc_code)
+        .map(|dlc| dlc.dlc_name)
+        .ok_or(APIError::CannotFindSong)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct APIRegisterResult {
     pub success: bool,
     pub update: bool,
 }
 
-pub fn register_record(
-    token: UserToken,
-    record: UserChartRecord,
-) -> Result<APIRegisterResult, APIError> {
-    let user_num = &token.user_num;
-
-    #[derive(Serialize, Deserialize, Debug)]
-    #[serde(rename_all = "camelCase")]
-    struct APIPlayRecord {
-        name: String,
-        dlc: String,
-        button: u8,
-        pattern: String,
-        score: f64,
-        max_combo: u8,
-    }
-
-    let dlc_name = get_full_dlc_name(record.song_cat.unwrap().to_string()).expect("No exist DLC");
-
-    let data_for_api: APIPlayRecord = APIPlayRecord {
-        name: record.title,
-        dlc: dlc_name,
-        button: u8::from(record.button),
-        pattern: record.chart_type.to_string(),
-        score: record.acc_rate.unwrap(),
-        max_combo: record.is_max_combo as u8,
-    };
-
-    let record_serial = serde_json::to_string(&data_for_api).unwrap();
-
-    let post_url = format!("https://v-archive.net/client/open/{user_num}/score");
-    let resp = ureq::post(&post_url)
-        .set("Authorization", &token.user_token)
-        .set("Content-Type", "application/json")
-        .send_string(&record_serial);
-
-    match resp {
-        Ok(resp) => {
-            let resp_str = resp.into_string().unwrap();
-            Ok(serde_json::from_str(&resp_str).unwrap())
-        }
-        Err(Error::Status(code, resp)) => Err(catch_server_err(code, resp)),
-        Err(_) => Err(APIError::UnknownError),
-    }
-}
-
+#[derive(Debug, Clone)]
 pub struct SongSet {
     pub content: Song,
     pub charts: Vec<Chart>,
@@ -1656,6 +1732,25 @@ impl SongSet {
             charts: Vec::new(),
         }
     }
+
+    /// A chart for `ChartRef` (`None` for a chart of another song)
+    pub fn chart(&self, chart: &ChartRef) -> Option<&Chart> {
+        if chart.song_id != self.content.song_id {
+            return None;
+        }
+
+        self.charts
+            .iter()
+            .find(|c| c.button == chart.button && c.chart_type == chart.chart_type)
+    }
+
+    /// `ChartRef`s for all charts of the song
+    pub fn chart_refs(&self) -> Vec<ChartRef> {
+        self.charts
+            .iter()
+            .map(|c| ChartRef::of(&self.content, c))
+            .collect()
+    }
 }
 
 fn all_songs_parse(parse_text: String) -> Vec<SongSet> {
@@ -2000,6 +2095,35 @@ pub fn board_types() -> Result<Vec<String>, APIError> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn legacy_category_to_string() {
+        assert_eq!(SongCatagory::from("TR").to_string(), "TR");
+        assert_eq!(SongCatagory::from("CE").to_string(), "CE");
+        assert_eq!(
+            SongCatagory::LegacyExtention(LegacyExtCat::Trilogy).to_string(),
+            "TR"
+        );
+        assert_eq!(
+            SongCatagory::LegacyExtention(LegacyExtCat::Clazziquai).to_string(),
+            "CE"
+        );
+    }
+
+    #[test]
+    fn server_error_bodies() {
+        let body = r#"{"success": false, "errorCode": 101, "message": "no user"}"#;
+        assert!(matches!(server_err_of(404, body), APIError::CannotFindUser));
+
+        // Not a JSON body, e. g. an HTML page from a proxy
+        let page = "<html><body>Bad Gateway</body></html>";
+        assert!(matches!(server_err_of(400, page), APIError::HTTPErr(400)));
+        assert!(matches!(server_err_of(502, page), APIError::HTTPErr(502)));
+        assert!(matches!(
+            server_err_of(500, ""),
+            APIError::InernalServerError
+        ));
+    }
+
     #[test]
     fn not_available_buttons() {
         // Loading tier info; as "10" buttons(which is **not available**) tier on DEV
@@ -2136,11 +2260,9 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "write")]
     fn register_record_test() {
-        let user = UserToken {
-            user_num: 1,
-            user_token: "95d6c422-52b4-4016-8587-38c46a2e7917".to_string(),
-        };
+        let user = UserToken::new(1, "95d6c422-52b4-4016-8587-38c46a2e7917".to_string()).unwrap();
 
         let play_record = UserChartRecord {
             song_id: 0,
@@ -2158,7 +2280,9 @@ mod tests {
             updated_at: None,
         };
 
-        let req = register_record(user, play_record);
+        let (req, _) = Client::new()
+            .authenticate(user)
+            .register_record(&play_record);
 
         match req {
             Ok(r) => assert_eq!(r.success, true),
@@ -0,0 +1,275 @@
+use crate::UserChartRecord;
+
+/// Tolerance for the near miss margin (e. g. `99.5 - 99.3` is a bit over `0.2` on `f64`)
+const MARGIN_EPSILON: f64 = 1e-9;
+
+/// Accuracy milestones (e. g. 99%, 99.5%, 99.8%, 100%) for counting records.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{load_user_tier, Milestones};
+/// #
+/// # fn main() {
+/// let table = load_user_tier("내꺼", 6).unwrap();
+/// let matrix = Milestones::default().by_level(&table.top_records);
+///
+/// for row in &matrix.rows {
+///     let counts: Vec<usize> = row.cells.iter().map(|c| c.count).collect();
+///     println!("{:?}: {:?} of {}", row.key, counts, row.total);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Milestones {
+    /// Accuracy thresholds, in ascending order
+    pub thresholds: Vec<f64>,
+    /// A record below a threshold by this margin (or less) is a near miss
+    pub near_miss_margin: f64,
+}
+
+impl Milestones {
+    /// Milestones for thresholds. The near miss margin is `0.2`.
+    pub fn new(mut thresholds: Vec<f64>) -> Self {
+        thresholds.sort_by(|a, b| a.total_cmp(b));
+        thresholds.dedup();
+
+        Self {
+            thresholds,
+            near_miss_margin: 0.2,
+        }
+    }
+
+    /// A matrix by `chart_level`. Records without it are skipped
+    /// (fill it with `enrich_records()`).
+    pub fn by_level<'a, I>(&self, records: I) -> MilestoneMatrix<'a>
+    where
+        I: IntoIterator<Item = &'a UserChartRecord>,
+    {
+        self.matrix(records, |r| r.chart_level.map(MilestoneKey::Level))
+    }
+
+    /// A matrix by `floor_level`. Records without it are skipped.
+    pub fn by_floor<'a, I>(&self, records: I) -> MilestoneMatrix<'a>
+    where
+        I: IntoIterator<Item = &'a UserChartRecord>,
+    {
+        self.matrix(records, |r| r.floor_level.map(MilestoneKey::Floor))
+    }
+
+    fn matrix<'a, I, F>(&self, records: I, key_of: F) -> MilestoneMatrix<'a>
+    where
+        I: IntoIterator<Item = &'a UserChartRecord>,
+        F: Fn(&UserChartRecord) -> Option<MilestoneKey>,
+    {
+        let mut rows: Vec<MilestoneRow<'a>> = Vec::new();
+        let mut skipped = 0;
+
+        for record in records {
+            let key = match key_of(record) {
+                Some(k) => k,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+
+            let index = match rows.iter().position(|r| r.key == key) {
+                Some(i) => i,
+                None => {
+                    rows.push(MilestoneRow::new(key, &self.thresholds));
+                    rows.len() - 1
+                }
+            };
+            rows[index].add(record, self.near_miss_margin);
+        }
+
+        rows.sort_by(|a, b| a.key.value().total_cmp(&b.key.value()));
+        for row in &mut rows {
+            for cell in &mut row.cells {
+                cell.near_misses.sort_by(|a, b| {
+                    b.acc_rate
+                        .unwrap_or(0.0)
+                        .total_cmp(&a.acc_rate.unwrap_or(0.0))
+                });
+            }
+        }
+
+        MilestoneMatrix {
+            thresholds: self.thresholds.clone(),
+            rows,
+            skipped,
+        }
+    }
+}
+
+impl Default for Milestones {
+    /// 99%, 99.5%, 99.8% and 100%
+    fn default() -> Self {
+        Self::new(vec![99.0, 99.5, 99.8, 100.0])
+    }
+}
+
+/// A row key of `MilestoneMatrix`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MilestoneKey {
+    Level(u8),
+    Floor(f64),
+}
+
+impl MilestoneKey {
+    fn value(&self) -> f64 {
+        match self {
+            Self::Level(l) => *l as f64,
+            Self::Floor(f) => *f,
+        }
+    }
+}
+
+/// Records for a threshold on a row
+#[derive(Debug, Clone)]
+pub struct MilestoneCell<'a> {
+    pub threshold: f64,
+    /// Records with accuracy at or above the threshold
+    pub count: usize,
+    /// Records just below the threshold, from the closest
+    pub near_misses: Vec<&'a UserChartRecord>,
+}
+
+/// A row (a level or a floor) of `MilestoneMatrix`
+#[derive(Debug, Clone)]
+pub struct MilestoneRow<'a> {
+    pub key: MilestoneKey,
+    /// All records on the row, with unplayed ones
+    pub total: usize,
+    pub played: usize,
+    /// Cells for each threshold, in order of `Milestones.thresholds`
+    pub cells: Vec<MilestoneCell<'a>>,
+}
+
+impl<'a> MilestoneRow<'a> {
+    fn new(key: MilestoneKey, thresholds: &[f64]) -> Self {
+        Self {
+            key,
+            total: 0,
+            played: 0,
+            cells: thresholds
+                .iter()
+                .map(|t| MilestoneCell {
+                    threshold: *t,
+                    count: 0,
+                    near_misses: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    fn add(&mut self, record: &'a UserChartRecord, margin: f64) {
+        self.total += 1;
+        let acc = match record.acc_rate {
+            Some(a) => a,
+            None => return,
+        };
+        self.played += 1;
+
+        for cell in &mut self.cells {
+            if acc >= cell.threshold {
+                cell.count += 1;
+            } else if cell.threshold - acc <= margin + MARGIN_EPSILON {
+                cell.near_misses.push(record);
+            }
+        }
+    }
+}
+
+/// Counts of records for each row and threshold, from `Milestones`
+#[derive(Debug, Clone)]
+pub struct MilestoneMatrix<'a> {
+    pub thresholds: Vec<f64>,
+    /// Rows in ascending order
+    pub rows: Vec<MilestoneRow<'a>>,
+    /// Records without a level (or floor)
+    pub skipped: usize,
+}
+
+impl<'a> MilestoneMatrix<'a> {
+    pub fn row(&self, key: MilestoneKey) -> Option<&MilestoneRow<'a>> {
+        self.rows.iter().find(|r| r.key == key)
+    }
+
+    /// Counts for each threshold on all rows
+    pub fn totals(&self) -> Vec<usize> {
+        (0..self.thresholds.len())
+            .map(|i| self.rows.iter().map(|r| r.cells[i].count).sum())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use crate::ChartType;
+
+    fn record(acc: Option<f64>, level: u8, floor: f64) -> UserChartRecord {
+        test_util::record(0, ChartType::Sc)
+            .with_acc(acc)
+            .with_level(level)
+            .with_floor(floor)
+    }
+
+    #[test]
+    fn milestone_matrix() {
+        let records = vec![
+            record(Some(99.45), 14, 14.2),
+            record(Some(99.65), 14, 14.2),
+            record(Some(100.0), 14, 14.5),
+            record(None, 14, 14.5),
+            record(Some(98.9), 13, 13.1),
+        ];
+
+        let matrix = Milestones::default().by_level(&records);
+        assert_eq!(matrix.rows.len(), 2);
+        assert_eq!(matrix.rows[0].key, MilestoneKey::Level(13));
+
+        let row = matrix.row(MilestoneKey::Level(14)).unwrap();
+        assert_eq!((row.total, row.played), (4, 3));
+        let counts: Vec<usize> = row.cells.iter().map(|c| c.count).collect();
+        assert_eq!(counts, vec![3, 2, 1, 1]);
+        // 99.45 is near 99.5, and 99.65 is near 99.8
+        assert_eq!(row.cells[1].near_misses[0].acc_rate, Some(99.45));
+        assert_eq!(row.cells[2].near_misses[0].acc_rate, Some(99.65));
+        assert!(row.cells[3].near_misses.is_empty());
+
+        assert_eq!(matrix.totals(), vec![3, 2, 1, 1]);
+        assert_eq!(
+            matrix.row(MilestoneKey::Level(13)).unwrap().cells[0]
+                .near_misses
+                .len(),
+            1
+        );
+
+        let mut milestones = Milestones::new(vec![99.5, 99.0]);
+        milestones.near_miss_margin = 0.5;
+        let matrix = milestones.by_floor(&records);
+        assert_eq!(matrix.thresholds, vec![99.0, 99.5]);
+        assert_eq!(matrix.rows.len(), 3);
+        assert_eq!(
+            matrix.row(MilestoneKey::Floor(14.2)).unwrap().cells[1]
+                .near_misses
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn near_miss_on_margin() {
+        let records = vec![record(Some(99.3), 14, 14.2), record(Some(99.8), 14, 14.2)];
+
+        let matrix = Milestones::default().by_level(&records);
+        let row = matrix.row(MilestoneKey::Level(14)).unwrap();
+        // Exactly 0.2 below 99.5 and 100.0
+        assert_eq!(row.cells[1].near_misses.len(), 1);
+        assert_eq!(row.cells[1].near_misses[0].acc_rate, Some(99.3));
+        assert_eq!(row.cells[3].near_misses.len(), 1);
+        assert_eq!(row.cells[3].near_misses[0].acc_rate, Some(99.8));
+    }
+}
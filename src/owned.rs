@@ -0,0 +1,167 @@
+use crate::{LegacyCat, RespectCat, Song, SongCatagory, SongSet, UserChartRecord};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Errors on loading `OwnedContent`
+#[derive(Debug)]
+pub enum OwnedContentError {
+    ParseError(String),
+    IOError(io::Error),
+}
+
+impl fmt::Display for OwnedContentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParseError(m) => write!(f, "Owned content file error: {}", m),
+            Self::IOError(e) => write!(f, "IO error: {}", e),
+        }
+    }
+}
+
+/// Song categories (DLCs) a player owns. Charts of other categories can be excluded on
+/// `Catalog::restrict()`, `SearchFilter.owned`, `ChartQuery::owned()` and statistics.
+///
+/// Base game categories (`RESPECT`, `RESPECT V`, `PORTABLE 1`, `PORTABLE 2`) are always owned.
+///
+/// A config file is TOML like:
+/// ```toml
+/// owned = ["VE", "VE2", "P3", "GG"]
+/// ```
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{Catalog, OwnedContent};
+/// #
+/// # fn main() {
+/// let owned = OwnedContent::load("owned.toml").unwrap();
+/// let catalog = Catalog::load().unwrap().restrict(&owned);
+///
+/// println!("Songs: {}", catalog.song_count());
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OwnedContent {
+    categories: HashSet<SongCatagory>,
+}
+
+impl OwnedContent {
+    /// Only base game categories
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load from a config file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, OwnedContentError> {
+        let text = fs::read_to_string(path).map_err(OwnedContentError::IOError)?;
+        Self::parse(&text)
+    }
+
+    /// Parse a config text (TOML)
+    pub fn parse(text: &str) -> Result<Self, OwnedContentError> {
+        #[derive(Deserialize)]
+        struct ConfigFile {
+            #[serde(default)]
+            owned: Vec<String>,
+        }
+
+        let config: ConfigFile = toml::from_str(text)
+            .map_err(|e| OwnedContentError::ParseError(e.message().to_owned()))?;
+
+        Ok(Self::from_categories(
+            config.owned.iter().map(|c| SongCatagory::from(c.as_str())),
+        ))
+    }
+
+    pub fn from_categories<I: IntoIterator<Item = SongCatagory>>(categories: I) -> Self {
+        Self {
+            categories: categories.into_iter().collect(),
+        }
+    }
+
+    /// Guess from played records: a category with a played record is owned.
+    /// For a record without `song_cat`, the song is found on `songs` by song ID.
+    pub fn infer<'a, I>(records: I, songs: &[SongSet]) -> Self
+    where
+        I: IntoIterator<Item = &'a UserChartRecord>,
+    {
+        let mut owned = Self::new();
+        for r in records.into_iter().filter(|r| r.acc_rate.is_some()) {
+            let category = match &r.song_cat {
+                Some(c) => Some(c.clone()),
+                None => songs
+                    .iter()
+                    .find(|s| s.content.song_id == r.song_id)
+                    .map(|s| s.content.song_cat.clone()),
+            };
+            if let Some(c) = category {
+                owned.add(c);
+            }
+        }
+        owned
+    }
+
+    pub fn add(&mut self, category: SongCatagory) {
+        self.categories.insert(category);
+    }
+
+    /// Owned categories, without base game categories
+    pub fn categories(&self) -> Vec<&SongCatagory> {
+        let mut categories: Vec<&SongCatagory> = self
+            .categories
+            .iter()
+            .filter(|c| !is_base_category(c))
+            .collect();
+        categories.sort_by_key(|c| c.to_string());
+        categories
+    }
+
+    pub fn owns(&self, category: &SongCatagory) -> bool {
+        is_base_category(category) || self.categories.contains(category)
+    }
+
+    pub fn owns_song(&self, song: &Song) -> bool {
+        self.owns(&song.song_cat)
+    }
+
+    /// Whether a record is for an owned song. A record without `song_cat` is counted as owned.
+    pub fn owns_record(&self, record: &UserChartRecord) -> bool {
+        record.song_cat.as_ref().is_none_or(|c| self.owns(c))
+    }
+}
+
+fn is_base_category(category: &SongCatagory) -> bool {
+    matches!(
+        category,
+        SongCatagory::Respect(RespectCat::Respect | RespectCat::RespectV)
+            | SongCatagory::Legacy(LegacyCat::PortableOne | LegacyCat::PortableTwo)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owned_content() {
+        let owned = OwnedContent::parse(r#"owned = ["VE", "GG"]"#).unwrap();
+        assert!(owned.owns(&SongCatagory::from("R")));
+        assert!(owned.owns(&SongCatagory::from("P2")));
+        assert!(owned.owns(&SongCatagory::from("GG")));
+        assert!(!owned.owns(&SongCatagory::from("VE2")));
+        assert_eq!(owned.categories().len(), 2);
+
+        let mut played = UserChartRecord::new();
+        played.acc_rate = Some(97.0);
+        played.song_cat = Some(SongCatagory::from("CY"));
+        let mut unplayed = UserChartRecord::new();
+        unplayed.song_cat = Some(SongCatagory::from("ARC"));
+
+        let inferred = OwnedContent::infer([&played, &unplayed], &[]);
+        assert!(inferred.owns(&SongCatagory::from("CY")));
+        assert!(!inferred.owns(&SongCatagory::from("ARC")));
+        assert!(!inferred.owns_record(&unplayed));
+    }
+}
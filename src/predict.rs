@@ -0,0 +1,284 @@
+use crate::{ButtonMode, Chart, UserChartRecord};
+
+/// z for 95% prediction intervals
+const Z_95: f64 = 1.96;
+
+/// Expected accuracy for a floor, from `AccuracyModel`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    pub acc_rate: f64,
+    /// 95% prediction interval for a single record on the floor (clamped to `0.0` to `100.0`)
+    pub low: f64,
+    pub high: f64,
+    /// Numbers of records the prediction is based on (in the nearest block)
+    pub samples: usize,
+}
+
+/// A record compared with its expected accuracy
+#[derive(Debug, Clone)]
+pub struct Residual<'a> {
+    pub record: &'a UserChartRecord,
+    pub expected: f64,
+    /// `acc_rate - expected`: positive if the user does better than expected
+    pub residual: f64,
+    /// Residual divided by standard deviation of residuals (`0.0` if it's unknown)
+    pub z_score: f64,
+}
+
+impl Residual<'_> {
+    /// Doing better than expected, by `z` standard deviations or more
+    pub fn is_over(&self, z: f64) -> bool {
+        self.z_score >= z
+    }
+
+    /// Doing worse than expected, by `z` standard deviations or more
+    pub fn is_under(&self, z: f64) -> bool {
+        self.z_score <= -z
+    }
+}
+
+/// Records merged while fitting; accuracy never goes up on higher blocks
+#[derive(Debug, Clone, PartialEq)]
+struct Block {
+    /// Mean floor of records
+    floor: f64,
+    acc_rate: f64,
+    samples: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ButtonFit {
+    button: ButtonMode,
+    /// Blocks in order of floor
+    blocks: Vec<Block>,
+    /// Standard deviation of residuals (`None` with too few records)
+    deviation: Option<f64>,
+}
+
+impl ButtonFit {
+    fn fit(button: ButtonMode, mut points: Vec<(f64, f64)>) -> Self {
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Pool adjacent violators: higher floors shouldn't have better accuracy
+        let mut blocks: Vec<Block> = Vec::new();
+        for (floor, acc) in &points {
+            blocks.push(Block {
+                floor: *floor,
+                acc_rate: *acc,
+                samples: 1,
+            });
+            while blocks.len() > 1 {
+                let last = &blocks[blocks.len() - 1];
+                let prev = &blocks[blocks.len() - 2];
+                if prev.floor < last.floor && prev.acc_rate >= last.acc_rate {
+                    break;
+                }
+                let last = blocks.pop().unwrap();
+                let prev = blocks.last_mut().unwrap();
+                let samples = (prev.samples + last.samples) as f64;
+                prev.floor =
+                    (prev.floor * prev.samples as f64 + last.floor * last.samples as f64) / samples;
+                prev.acc_rate = (prev.acc_rate * prev.samples as f64
+                    + last.acc_rate * last.samples as f64)
+                    / samples;
+                prev.samples += last.samples;
+            }
+        }
+
+        let mut fit = Self {
+            button,
+            blocks,
+            deviation: None,
+        };
+        let freedom = points.len().saturating_sub(fit.blocks.len());
+        if freedom > 0 {
+            let squares: f64 = points
+                .iter()
+                .map(|(floor, acc)| (acc - fit.expected(*floor).0).powi(2))
+                .sum();
+            fit.deviation = Some((squares / freedom as f64).sqrt());
+        }
+        fit
+    }
+
+    /// Expected accuracy, interpolated between blocks, and samples of the nearest block.
+    /// `floor` should not be NaN.
+    fn expected(&self, floor: f64) -> (f64, usize) {
+        let first = &self.blocks[0];
+        let last = &self.blocks[self.blocks.len() - 1];
+        if floor <= first.floor {
+            return (first.acc_rate, first.samples);
+        }
+        if floor >= last.floor {
+            return (last.acc_rate, last.samples);
+        }
+
+        let upper = self.blocks.partition_point(|b| b.floor < floor);
+        let (a, b) = (&self.blocks[upper - 1], &self.blocks[upper]);
+        let t = (floor - a.floor) / (b.floor - a.floor);
+        let nearest = if t < 0.5 { a } else { b };
+        (a.acc_rate + (b.acc_rate - a.acc_rate) * t, nearest.samples)
+    }
+}
+
+/// A per-user model of accuracy against floor, for each button mode.
+///
+/// It's an isotonic regression: expected accuracy never goes up on higher floors.
+/// Predictions between fitted points are interpolated, and predictions outside them
+/// are the nearest fitted accuracy.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{load_user_floor_board, AccuracyModel, ButtonMode};
+/// #
+/// # fn main() {
+/// let board = load_user_floor_board("내꺼", 6, "SC").unwrap();
+/// let records: Vec<_> = board.floors.iter().flat_map(|f| f.records.iter()).collect();
+/// let model = AccuracyModel::fit(records.iter().copied());
+///
+/// if let Some(p) = model.predict(&ButtonMode::Six, 14.5) {
+///     println!("{:.2}% ({:.2}% ~ {:.2}%)", p.acc_rate, p.low, p.high);
+/// }
+/// for r in model.residuals(records).iter().filter(|r| r.is_under(2.0)) {
+///     println!("{}: {:.2}% (expected {:.2}%)", r.record.title, r.residual, r.expected);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccuracyModel {
+    fits: Vec<ButtonFit>,
+}
+
+impl AccuracyModel {
+    /// Fit with played records with `floor_level`. Other records are skipped.
+    pub fn fit<'a, I>(records: I) -> Self
+    where
+        I: IntoIterator<Item = &'a UserChartRecord>,
+    {
+        let mut points: Vec<(ButtonMode, Vec<(f64, f64)>)> = Vec::new();
+        for r in records {
+            let (acc, floor) = match (r.acc_rate, r.floor_level) {
+                (Some(a), Some(f)) => (a, f),
+                _ => continue,
+            };
+            match points.iter_mut().find(|(b, _)| b == &r.button) {
+                Some((_, p)) => p.push((floor, acc)),
+                None => points.push((r.button.clone(), vec![(floor, acc)])),
+            }
+        }
+
+        let mut fits: Vec<ButtonFit> = points
+            .into_iter()
+            .map(|(button, p)| ButtonFit::fit(button, p))
+            .collect();
+        fits.sort_by(|a, b| a.button.cmp(&b.button));
+        Self { fits }
+    }
+
+    /// Button modes with fitted records
+    pub fn buttons(&self) -> Vec<&ButtonMode> {
+        self.fits.iter().map(|f| &f.button).collect()
+    }
+
+    /// Expected accuracy on a floor (`None` without records for the button mode, or for a floor
+    /// which is not finite)
+    pub fn predict(&self, button: &ButtonMode, floor: f64) -> Option<Prediction> {
+        if !floor.is_finite() {
+            return None;
+        }
+        let fit = self.fits.iter().find(|f| &f.button == button)?;
+        let (acc_rate, samples) = fit.expected(floor);
+        let margin = fit
+            .deviation
+            .map_or(0.0, |d| Z_95 * d * (1.0 + 1.0 / samples as f64).sqrt());
+
+        Some(Prediction {
+            acc_rate,
+            low: (acc_rate - margin).clamp(0.0, 100.0),
+            high: (acc_rate + margin).clamp(0.0, 100.0),
+            samples,
+        })
+    }
+
+    /// Expected accuracy on a chart (`None` for a chart without floor)
+    pub fn predict_chart(&self, chart: &Chart) -> Option<Prediction> {
+        self.predict(&chart.button, chart.floor?)
+    }
+
+    /// Residuals of played records with `floor_level` (finite), from the best (over-performing) one
+    pub fn residuals<'a, I>(&self, records: I) -> Vec<Residual<'a>>
+    where
+        I: IntoIterator<Item = &'a UserChartRecord>,
+    {
+        let mut residuals: Vec<Residual<'a>> = records
+            .into_iter()
+            .filter_map(|record| {
+                let fit = self.fits.iter().find(|f| f.button == record.button)?;
+                let floor = record.floor_level.filter(|f| f.is_finite())?;
+                let expected = fit.expected(floor).0;
+                let residual = record.acc_rate? - expected;
+                Some(Residual {
+                    record,
+                    expected,
+                    residual,
+                    z_score: match fit.deviation {
+                        Some(d) if d > 0.0 => residual / d,
+                        _ => 0.0,
+                    },
+                })
+            })
+            .collect();
+        residuals.sort_by(|a, b| b.residual.total_cmp(&a.residual));
+        residuals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use crate::ChartType;
+
+    fn record(acc: Option<f64>, floor: f64) -> UserChartRecord {
+        test_util::record(0, ChartType::Sc)
+            .with_acc(acc)
+            .with_floor(floor)
+    }
+
+    #[test]
+    fn accuracy_model() {
+        let records = vec![
+            record(Some(99.5), 13.0),
+            record(Some(99.3), 13.0),
+            record(Some(98.0), 14.0),
+            record(Some(98.4), 14.0),
+            // Better than 14.0, so it's merged with them
+            record(Some(98.6), 14.5),
+            record(Some(96.0), 15.0),
+            record(None, 15.0),
+        ];
+        let model = AccuracyModel::fit(&records);
+        assert_eq!(model.buttons(), vec![&ButtonMode::Six]);
+        assert!(model.predict(&ButtonMode::Four, 14.0).is_none());
+        assert!(model.predict(&ButtonMode::Six, f64::NAN).is_none());
+        assert!(model.predict(&ButtonMode::Six, f64::INFINITY).is_none());
+
+        let low = model.predict(&ButtonMode::Six, 12.0).unwrap();
+        assert!((low.acc_rate - 99.4).abs() < 1e-9);
+        assert_eq!(low.samples, 2);
+        assert!(low.low < 99.4 && low.high > 99.4);
+
+        let middle = model.predict(&ButtonMode::Six, 13.5).unwrap();
+        assert!((middle.acc_rate - 98.942857).abs() < 1e-6);
+        let high = model.predict(&ButtonMode::Six, 16.0).unwrap();
+        assert_eq!(high.acc_rate, 96.0);
+        assert!(high.high - high.low > low.high - low.low);
+
+        let residuals = model.residuals(&records);
+        assert_eq!(residuals.len(), 6);
+        assert_eq!(residuals[0].record.acc_rate, Some(98.6));
+        assert_eq!(residuals[5].record.acc_rate, Some(98.0));
+        assert!(residuals[0].is_over(1.0));
+        assert!(residuals[5].is_under(0.5));
+        assert!(!residuals[5].is_over(0.0));
+    }
+}
@@ -0,0 +1,953 @@
+use crate::{
+    ButtonMode, Chart, ChartType, ClearLamp, OwnedContent, Song, SongCatagory, SongSet,
+    UserChartRecord,
+};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Fields of a chart (and its song) for `ChartQuery`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryField {
+    /// `Chart.button` as a number (`button`, `b`)
+    Button,
+    /// `Chart.chart_type` (`type`, `chart`)
+    ChartType,
+    /// `Chart.level` (`level`, `lv`)
+    Level,
+    /// `Chart.floor` (`floor`)
+    Floor,
+    /// `Chart.rating` (`rating`). For a record, `UserChartRecord.user_rating`.
+    Rating,
+    /// `Song.song_cat` (`dlc`, `cat`, `category`)
+    Category,
+    /// `UserChartRecord.acc_rate` (`acc`, `accuracy`), only for records
+    Accuracy,
+    /// `ClearLamp` of a record (`lamp`), only for records
+    Lamp,
+}
+
+impl QueryField {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "button" | "b" => Some(Self::Button),
+            "type" | "chart" => Some(Self::ChartType),
+            "level" | "lv" => Some(Self::Level),
+            "floor" => Some(Self::Floor),
+            "rating" => Some(Self::Rating),
+            "dlc" | "cat" | "category" => Some(Self::Category),
+            "acc" | "accuracy" => Some(Self::Accuracy),
+            "lamp" => Some(Self::Lamp),
+            _ => None,
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        !matches!(self, Self::ChartType | Self::Category)
+    }
+}
+
+/// A row for `ChartQuery`: a chart of a song, or a user's record
+trait QueryRow {
+    fn number(&self, field: QueryField) -> Option<f64>;
+    fn text(&self, field: QueryField) -> Option<String>;
+    fn title(&self) -> &str;
+    fn song_id(&self) -> usize;
+}
+
+impl QueryRow for (&Song, &Chart) {
+    fn number(&self, field: QueryField) -> Option<f64> {
+        let chart = self.1;
+        match field {
+            QueryField::Button => Some(u8::from(chart.button.clone()) as f64),
+            QueryField::Level => Some(chart.level as f64),
+            QueryField::Floor => chart.floor,
+            QueryField::Rating => chart.rating,
+            _ => None,
+        }
+    }
+
+    fn text(&self, field: QueryField) -> Option<String> {
+        match field {
+            QueryField::ChartType => Some(self.1.chart_type.to_string()),
+            QueryField::Category => Some(self.0.song_cat.to_string()),
+            _ => None,
+        }
+    }
+
+    fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    fn song_id(&self) -> usize {
+        self.0.song_id
+    }
+}
+
+impl QueryRow for &UserChartRecord {
+    fn number(&self, field: QueryField) -> Option<f64> {
+        match field {
+            QueryField::Button => Some(u8::from(self.button.clone()) as f64),
+            QueryField::Level => self.chart_level.map(|l| l as f64),
+            QueryField::Floor => self.floor_level,
+            QueryField::Rating => self.user_rating,
+            QueryField::Accuracy => self.acc_rate,
+            QueryField::Lamp => Some(self.clear_lamp() as u8 as f64),
+            _ => None,
+        }
+    }
+
+    fn text(&self, field: QueryField) -> Option<String> {
+        match field {
+            QueryField::ChartType => Some(self.chart_type.to_string()),
+            QueryField::Category => self.song_cat.as_ref().map(|c| c.to_string()),
+            _ => None,
+        }
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn song_id(&self) -> usize {
+        self.song_id
+    }
+}
+
+impl fmt::Display for QueryField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Button => write!(f, "button"),
+            Self::ChartType => write!(f, "type"),
+            Self::Level => write!(f, "level"),
+            Self::Floor => write!(f, "floor"),
+            Self::Rating => write!(f, "rating"),
+            Self::Category => write!(f, "dlc"),
+            Self::Accuracy => write!(f, "acc"),
+            Self::Lamp => write!(f, "lamp"),
+        }
+    }
+}
+
+/// Comparison operators for `ChartQuery`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn test(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering == Ordering::Equal,
+            Self::Ne => ordering != Ordering::Equal,
+            Self::Lt => ordering == Ordering::Less,
+            Self::Le => ordering != Ordering::Greater,
+            Self::Gt => ordering == Ordering::Greater,
+            Self::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// Keys for sorting results of `ChartQuery`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Level,
+    Floor,
+    Rating,
+    Button,
+    Title,
+    SongId,
+    /// Only for records
+    Accuracy,
+    /// Only for records
+    Lamp,
+}
+
+impl SortKey {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "level" | "lv" => Some(Self::Level),
+            "floor" => Some(Self::Floor),
+            "rating" => Some(Self::Rating),
+            "button" | "b" => Some(Self::Button),
+            "title" => Some(Self::Title),
+            "id" => Some(Self::SongId),
+            "acc" | "accuracy" => Some(Self::Accuracy),
+            "lamp" => Some(Self::Lamp),
+            _ => None,
+        }
+    }
+
+    fn compare<R: QueryRow>(self, a: &R, b: &R) -> Option<Ordering> {
+        let number = |field: QueryField| match (a.number(field), b.number(field)) {
+            (Some(x), Some(y)) => Some(x.partial_cmp(&y).unwrap_or(Ordering::Equal)),
+            _ => None,
+        };
+
+        match self {
+            Self::Level => number(QueryField::Level),
+            Self::Floor => number(QueryField::Floor),
+            Self::Rating => number(QueryField::Rating),
+            Self::Button => number(QueryField::Button),
+            Self::Accuracy => number(QueryField::Accuracy),
+            Self::Lamp => number(QueryField::Lamp),
+            Self::Title => Some(a.title().cmp(b.title())),
+            Self::SongId => Some(a.song_id().cmp(&b.song_id())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Compare {
+        field: QueryField,
+        comparison: Comparison,
+        value: f64,
+    },
+    Range {
+        field: QueryField,
+        min: f64,
+        max: f64,
+    },
+    NumberIn {
+        field: QueryField,
+        values: Vec<f64>,
+    },
+    TextIn {
+        field: QueryField,
+        values: Vec<String>,
+        negate: bool,
+    },
+    Owned(OwnedContent),
+}
+
+impl Condition {
+    fn matches<R: QueryRow>(&self, row: &R) -> bool {
+        match self {
+            Self::Compare {
+                field,
+                comparison,
+                value,
+            } => row
+                .number(*field)
+                .and_then(|n| n.partial_cmp(value))
+                .is_some_and(|o| comparison.test(o)),
+            Self::Range { field, min, max } => {
+                row.number(*field).is_some_and(|n| *min <= n && n <= *max)
+            }
+            Self::NumberIn { field, values } => {
+                row.number(*field).is_some_and(|n| values.contains(&n))
+            }
+            Self::TextIn {
+                field,
+                values,
+                negate,
+            } => row
+                .text(*field)
+                .is_some_and(|t| values.iter().any(|v| v.eq_ignore_ascii_case(&t)) != *negate),
+            // A record without a category is counted as owned
+            Self::Owned(owned) => row
+                .text(QueryField::Category)
+                .is_none_or(|c| owned.owns(&SongCatagory::from(c.as_str()))),
+        }
+    }
+}
+
+/// Kinds of `QueryError`
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryErrorKind {
+    UnexpectedChar(char),
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    UnknownField(String),
+    UnknownSortKey(String),
+    InvalidValue(String),
+    /// The operator is not for the field (e. g. `type < SC`)
+    InvalidOperator(QueryField),
+}
+
+/// An error on parsing a query, with the position (index of character) on the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub position: usize,
+    pub kind: QueryErrorKind,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            QueryErrorKind::UnexpectedChar(c) => write!(f, "Unexpected character '{}'", c),
+            QueryErrorKind::UnexpectedToken(t) => write!(f, "Unexpected \"{}\"", t),
+            QueryErrorKind::UnexpectedEnd => write!(f, "Unexpected end of query"),
+            QueryErrorKind::UnknownField(n) => write!(f, "Unknown field \"{}\"", n),
+            QueryErrorKind::UnknownSortKey(n) => write!(f, "Unknown sort key \"{}\"", n),
+            QueryErrorKind::InvalidValue(v) => write!(f, "Invalid value \"{}\"", v),
+            QueryErrorKind::InvalidOperator(field) => {
+                write!(f, "Invalid operator for \"{}\"", field)
+            }
+        }?;
+        write!(f, " at {}", self.position)
+    }
+}
+
+/// A query for charts on a song list (from `all_songs()`), made by the builder functions or
+/// parsed from text.
+///
+/// A query text is conditions joined with `and`, and optional `sort` and `limit`:
+/// ```text
+/// button = 6 and type = SC and level in 13..15 and dlc in (VE, VL) and floor >= 14
+///     sort floor desc, title limit 20
+/// ```
+/// * Fields: `button` (`6` or `6B`), `type` (`NM`, `HD`, `MX`, `SC`), `level`, `floor`,
+///   `rating`, `dlc` (a category code like `VE`)
+/// * Fields only for records (`ChartQuery::filter_records()`): `acc`, `lamp` (`unplayed`,
+///   `played`, `maxcombo`, `perfect`; see `ClearLamp`)
+/// * Operators: `=`, `!=`, `<`, `<=`, `>`, `>=` (only `=` and `!=` for `type` and `dlc`),
+///   `in (a, b, ...)`, `in min..max`
+/// * Sort keys: `level`, `floor`, `rating`, `button`, `title`, `id`, `acc`, `lamp`
+///   (with `asc` or `desc`)
+///
+/// A chart without floor (or rating) never matches conditions for floor (or rating).
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{all_songs, ButtonMode, ChartQuery, ChartType, SortKey, SortOrder};
+/// #
+/// # fn main() {
+/// let songs = all_songs().unwrap();
+///
+/// let query: ChartQuery = "button = 6 and type = SC and level in 13..15 sort floor desc"
+///     .parse()
+///     .unwrap();
+/// // Same as
+/// let built = ChartQuery::new()
+///     .button(ButtonMode::Six)
+///     .chart_type(ChartType::Sc)
+///     .levels(13, 15)
+///     .sort_by(SortKey::Floor, SortOrder::Desc);
+/// assert_eq!(query, built);
+///
+/// for (song, chart) in query.run(&songs) {
+///     println!("{} {}B {} ({:?})", song.title, chart.button, chart.chart_type, chart.floor);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChartQuery {
+    conditions: Vec<Condition>,
+    sort: Vec<(SortKey, SortOrder)>,
+    limit: Option<usize>,
+}
+
+impl ChartQuery {
+    /// A query for all charts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a query text
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        Parser::new(text)?.parse_query()
+    }
+
+    /// Only charts for the button mode
+    pub fn button(self, button: ButtonMode) -> Self {
+        self.compare(QueryField::Button, Comparison::Eq, u8::from(button) as f64)
+    }
+
+    pub fn chart_type(self, chart_type: ChartType) -> Self {
+        self.chart_types([chart_type])
+    }
+
+    /// Only charts of one of the types
+    pub fn chart_types<I: IntoIterator<Item = ChartType>>(mut self, chart_types: I) -> Self {
+        self.conditions.push(Condition::TextIn {
+            field: QueryField::ChartType,
+            values: chart_types.into_iter().map(|t| t.to_string()).collect(),
+            negate: false,
+        });
+        self
+    }
+
+    /// Only charts of songs in one of the categories
+    pub fn categories<I: IntoIterator<Item = SongCatagory>>(mut self, categories: I) -> Self {
+        self.conditions.push(Condition::TextIn {
+            field: QueryField::Category,
+            values: categories.into_iter().map(|c| c.to_string()).collect(),
+            negate: false,
+        });
+        self
+    }
+
+    /// Only charts with a level from `min` to `max`
+    pub fn levels(self, min: u8, max: u8) -> Self {
+        self.range(QueryField::Level, min as f64, max as f64)
+    }
+
+    /// Only charts with a floor from `min` to `max`
+    pub fn floors(self, min: f64, max: f64) -> Self {
+        self.range(QueryField::Floor, min, max)
+    }
+
+    /// Only charts with a number field in a range (both ends included)
+    pub fn range(mut self, field: QueryField, min: f64, max: f64) -> Self {
+        self.conditions.push(Condition::Range { field, min, max });
+        self
+    }
+
+    /// Only charts with a number field compared to a value (e. g. `floor >= 14.0`).
+    /// It never matches for `QueryField::ChartType` and `QueryField::Category`.
+    pub fn compare(mut self, field: QueryField, comparison: Comparison, value: f64) -> Self {
+        self.conditions.push(Condition::Compare {
+            field,
+            comparison,
+            value,
+        });
+        self
+    }
+
+    /// Sort results by a key. Keys added later are used for ties.
+    pub fn sort_by(mut self, key: SortKey, order: SortOrder) -> Self {
+        self.sort.push((key, order));
+        self
+    }
+
+    /// The maximum number of results
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Only charts of songs the player owns
+    pub fn owned(mut self, owned: &OwnedContent) -> Self {
+        self.conditions.push(Condition::Owned(owned.clone()));
+        self
+    }
+
+    /// Only records with the lamp or better. It never matches for charts.
+    pub fn min_lamp(self, lamp: ClearLamp) -> Self {
+        self.compare(QueryField::Lamp, Comparison::Ge, lamp as u8 as f64)
+    }
+
+    /// Whether a chart matches all conditions
+    pub fn matches(&self, song: &Song, chart: &Chart) -> bool {
+        self.conditions.iter().all(|c| c.matches(&(song, chart)))
+    }
+
+    /// Whether a record matches all conditions
+    pub fn matches_record(&self, record: &UserChartRecord) -> bool {
+        self.conditions.iter().all(|c| c.matches(&record))
+    }
+
+    /// Find charts on a song list. Without sort keys, results are in order of the list.
+    pub fn run<'a>(&self, songs: &'a [SongSet]) -> Vec<(&'a Song, &'a Chart)> {
+        let mut found: Vec<(&Song, &Chart)> = songs
+            .iter()
+            .flat_map(|s| s.charts.iter().map(move |c| (&s.content, c)))
+            .filter(|(s, c)| self.matches(s, c))
+            .collect();
+
+        if !self.sort.is_empty() {
+            found.sort_by(|a, b| self.compare_rows(a, b));
+        }
+        if let Some(limit) = self.limit {
+            found.truncate(limit);
+        }
+
+        found
+    }
+
+    /// Find records with the query. Fields are from a record (e. g. `level` is `chart_level`,
+    /// `rating` is `user_rating`), so fill them with `enrich_records()` first.
+    /// ## Example
+    /// ```no_run
+    /// # use v_archive_rs::{load_user_floor_board, ChartQuery};
+    /// #
+    /// # fn main() {
+    /// let board = load_user_floor_board("내꺼", 6, "SC").unwrap();
+    /// let records = board.floors.iter().flat_map(|f| f.records.iter());
+    ///
+    /// let query = ChartQuery::parse("lamp < maxcombo and floor >= 14 sort acc desc").unwrap();
+    /// for r in query.filter_records(records) {
+    ///     println!("{} ({:?})", r.title, r.acc_rate);
+    /// }
+    /// # }
+    /// ```
+    pub fn filter_records<'a, I>(&self, records: I) -> Vec<&'a UserChartRecord>
+    where
+        I: IntoIterator<Item = &'a UserChartRecord>,
+    {
+        let mut found: Vec<&UserChartRecord> = records
+            .into_iter()
+            .filter(|r| self.matches_record(r))
+            .collect();
+
+        if !self.sort.is_empty() {
+            found.sort_by(|a, b| self.compare_rows(a, b));
+        }
+        if let Some(limit) = self.limit {
+            found.truncate(limit);
+        }
+
+        found
+    }
+
+    fn compare_rows<R: QueryRow>(&self, a: &R, b: &R) -> Ordering {
+        for (key, order) in &self.sort {
+            let ordering = match key.compare(a, b) {
+                Some(o) if *order == SortOrder::Desc => o.reverse(),
+                Some(o) => o,
+                // A missing value goes last on both orders
+                None => match (key.compare(a, a), key.compare(b, b)) {
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    _ => Ordering::Equal,
+                },
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+
+        Ordering::Equal
+    }
+}
+
+impl FromStr for ChartQuery {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, QueryError> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Number(f64),
+    Operator(Comparison),
+    LeftParen,
+    RightParen,
+    Comma,
+    Range,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    text: String,
+    position: usize,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let start = index;
+        let c = chars[index];
+        let next = chars.get(index + 1).copied();
+
+        let kind = if c.is_whitespace() {
+            index += 1;
+            continue;
+        } else if c.is_ascii_digit() {
+            while index < chars.len() && chars[index].is_ascii_digit() {
+                index += 1;
+            }
+            // A fraction, but not a range like `13..15`
+            if chars.get(index) == Some(&'.')
+                && chars.get(index + 1).is_some_and(|c| c.is_ascii_digit())
+            {
+                index += 1;
+                while index < chars.len() && chars[index].is_ascii_digit() {
+                    index += 1;
+                }
+            }
+            // A word starts with a number, like `6B`
+            if chars.get(index).is_some_and(|c| c.is_alphabetic()) {
+                while index < chars.len() && is_word_char(chars[index]) {
+                    index += 1;
+                }
+                TokenKind::Word(chars[start..index].iter().collect())
+            } else {
+                let number: String = chars[start..index].iter().collect();
+                TokenKind::Number(number.parse().expect("digits should be a number"))
+            }
+        } else if is_word_char(c) {
+            while index < chars.len() && is_word_char(chars[index]) {
+                index += 1;
+            }
+            TokenKind::Word(chars[start..index].iter().collect())
+        } else {
+            let (kind, length) = match (c, next) {
+                ('(', _) => (TokenKind::LeftParen, 1),
+                (')', _) => (TokenKind::RightParen, 1),
+                (',', _) => (TokenKind::Comma, 1),
+                ('.', Some('.')) => (TokenKind::Range, 2),
+                ('=', Some('=')) => (TokenKind::Operator(Comparison::Eq), 2),
+                ('=', _) => (TokenKind::Operator(Comparison::Eq), 1),
+                ('!', Some('=')) => (TokenKind::Operator(Comparison::Ne), 2),
+                ('<', Some('=')) => (TokenKind::Operator(Comparison::Le), 2),
+                ('<', _) => (TokenKind::Operator(Comparison::Lt), 1),
+                ('>', Some('=')) => (TokenKind::Operator(Comparison::Ge), 2),
+                ('>', _) => (TokenKind::Operator(Comparison::Gt), 1),
+                _ => {
+                    return Err(QueryError {
+                        position: start,
+                        kind: QueryErrorKind::UnexpectedChar(c),
+                    })
+                }
+            };
+            index += length;
+            kind
+        };
+
+        tokens.push(Token {
+            kind,
+            text: chars[start..index].iter().collect(),
+            position: start,
+        });
+    }
+
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    /// The length of the query, for errors at the end
+    end: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Result<Self, QueryError> {
+        Ok(Self {
+            tokens: tokenize(text)?,
+            index: 0,
+            end: text.chars().count(),
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Result<Token, QueryError> {
+        let token = self.tokens.get(self.index).cloned().ok_or(QueryError {
+            position: self.end,
+            kind: QueryErrorKind::UnexpectedEnd,
+        })?;
+        self.index += 1;
+        Ok(token)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Word(w), .. }) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        let found = self.peek().is_some_and(|t| &t.kind == kind);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, kind: &TokenKind) -> Result<(), QueryError> {
+        let token = self.next()?;
+        if &token.kind == kind {
+            Ok(())
+        } else {
+            Err(unexpected(&token))
+        }
+    }
+
+    fn parse_query(mut self) -> Result<ChartQuery, QueryError> {
+        let mut query = ChartQuery::new();
+
+        if self.peek().is_some() && !self.is_keyword("sort") && !self.is_keyword("limit") {
+            query.conditions.push(self.parse_condition()?);
+            while self.eat_keyword("and") {
+                query.conditions.push(self.parse_condition()?);
+            }
+        }
+
+        if self.eat_keyword("sort") {
+            self.eat_keyword("by");
+            loop {
+                let token = self.next()?;
+                let key = match &token.kind {
+                    TokenKind::Word(w) => SortKey::from_name(w).ok_or(QueryError {
+                        position: token.position,
+                        kind: QueryErrorKind::UnknownSortKey(w.clone()),
+                    })?,
+                    _ => return Err(unexpected(&token)),
+                };
+                let order = if self.eat_keyword("desc") {
+                    SortOrder::Desc
+                } else {
+                    self.eat_keyword("asc");
+                    SortOrder::Asc
+                };
+                query.sort.push((key, order));
+
+                if !self.eat(&TokenKind::Comma) {
+                    break;
+                }
+            }
+        }
+
+        if self.eat_keyword("limit") {
+            let token = self.next()?;
+            match token.kind {
+                TokenKind::Number(n) if n.fract() == 0.0 => query.limit = Some(n as usize),
+                _ => return Err(invalid_value(&token)),
+            }
+        }
+
+        match self.peek() {
+            Some(token) => Err(unexpected(token)),
+            None => Ok(query),
+        }
+    }
+
+    fn parse_condition(&mut self) -> Result<Condition, QueryError> {
+        let token = self.next()?;
+        let field = match &token.kind {
+            TokenKind::Word(w) => QueryField::from_name(w).ok_or(QueryError {
+                position: token.position,
+                kind: QueryErrorKind::UnknownField(w.clone()),
+            })?,
+            _ => return Err(unexpected(&token)),
+        };
+
+        let operator = self.next()?;
+        match operator.kind {
+            TokenKind::Operator(comparison) if field.is_numeric() => Ok(Condition::Compare {
+                field,
+                comparison,
+                value: self.parse_number(field)?,
+            }),
+            TokenKind::Operator(comparison @ (Comparison::Eq | Comparison::Ne)) => {
+                Ok(Condition::TextIn {
+                    field,
+                    values: vec![self.parse_text(field)?],
+                    negate: comparison == Comparison::Ne,
+                })
+            }
+            TokenKind::Operator(_) => Err(QueryError {
+                position: operator.position,
+                kind: QueryErrorKind::InvalidOperator(field),
+            }),
+            TokenKind::Word(w) if w.eq_ignore_ascii_case("in") => self.parse_in(field),
+            _ => Err(unexpected(&operator)),
+        }
+    }
+
+    /// After `in`: `(a, b, ...)` or `min..max`
+    fn parse_in(&mut self, field: QueryField) -> Result<Condition, QueryError> {
+        if field.is_numeric() && !self.eat(&TokenKind::LeftParen) {
+            let min = self.parse_number(field)?;
+            self.expect(&TokenKind::Range)?;
+            let max = self.parse_number(field)?;
+            return Ok(Condition::Range { field, min, max });
+        }
+        if !field.is_numeric() {
+            self.expect(&TokenKind::LeftParen)?;
+        }
+
+        let mut numbers = Vec::new();
+        let mut texts = Vec::new();
+        loop {
+            if field.is_numeric() {
+                numbers.push(self.parse_number(field)?);
+            } else {
+                texts.push(self.parse_text(field)?);
+            }
+            if !self.eat(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(&TokenKind::RightParen)?;
+
+        if field.is_numeric() {
+            Ok(Condition::NumberIn {
+                field,
+                values: numbers,
+            })
+        } else {
+            Ok(Condition::TextIn {
+                field,
+                values: texts,
+                negate: false,
+            })
+        }
+    }
+
+    fn parse_number(&mut self, field: QueryField) -> Result<f64, QueryError> {
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Number(n) => Ok(*n),
+            TokenKind::Word(w) if field == QueryField::Lamp => ClearLamp::from_name(w)
+                .map(|l| l as u8 as f64)
+                .ok_or(invalid_value(&token)),
+            // `6B` for buttons
+            TokenKind::Word(w) if field == QueryField::Button => w
+                .strip_suffix(['B', 'b'])
+                .and_then(|n| n.parse::<u8>().ok())
+                .map(|n| n as f64)
+                .ok_or(invalid_value(&token)),
+            _ => Err(invalid_value(&token)),
+        }
+    }
+
+    fn parse_text(&mut self, field: QueryField) -> Result<String, QueryError> {
+        let token = self.next()?;
+        let text = match &token.kind {
+            TokenKind::Word(w) => w.to_uppercase(),
+            _ => return Err(invalid_value(&token)),
+        };
+
+        if field == QueryField::ChartType
+            && matches!(ChartType::from(text.as_str()), ChartType::Other(_))
+        {
+            return Err(invalid_value(&token));
+        }
+
+        Ok(text)
+    }
+}
+
+fn unexpected(token: &Token) -> QueryError {
+    QueryError {
+        position: token.position,
+        kind: QueryErrorKind::UnexpectedToken(token.text.clone()),
+    }
+}
+
+fn invalid_value(token: &Token) -> QueryError {
+    QueryError {
+        position: token.position,
+        kind: QueryErrorKind::InvalidValue(token.text.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{record, songs};
+
+    #[test]
+    fn query_text_and_builder() {
+        let songs = songs();
+
+        let query: ChartQuery =
+            "button = 6B and type = sc and level in 13..15 and dlc in (ES, CY) and floor >= 14 sort floor desc limit 5"
+                .parse()
+                .unwrap();
+        let built = ChartQuery::new()
+            .button(ButtonMode::Six)
+            .chart_type(ChartType::Sc)
+            .levels(13, 15)
+            .categories([SongCatagory::from("ES"), SongCatagory::from("CY")])
+            .compare(QueryField::Floor, Comparison::Ge, 14.0)
+            .sort_by(SortKey::Floor, SortOrder::Desc)
+            .limit(5);
+
+        assert_eq!(query, built);
+        let found: Vec<usize> = query.run(&songs).iter().map(|(s, _)| s.song_id).collect();
+        assert_eq!(found, vec![4, 3]);
+
+        // A missing floor goes last
+        let charts = ChartQuery::parse("type = SC sort floor desc, button")
+            .unwrap()
+            .run(&songs);
+        assert_eq!(charts.len(), 13);
+        assert_eq!(charts[0].1.floor, Some(15.0));
+        assert_eq!(charts[12].1.floor, None);
+
+        assert_eq!(ChartQuery::parse("").unwrap().run(&songs).len(), 22);
+        assert_eq!(ChartQuery::parse("limit 2").unwrap().run(&songs).len(), 2);
+    }
+
+    #[test]
+    fn query_errors() {
+        let error = |text: &str| ChartQuery::parse(text).unwrap_err();
+
+        assert_eq!(
+            error("level >= 13 and speed = 2"),
+            QueryError {
+                position: 16,
+                kind: QueryErrorKind::UnknownField("speed".to_string()),
+            }
+        );
+        assert_eq!(
+            error("type < SC").kind,
+            QueryErrorKind::InvalidOperator(QueryField::ChartType)
+        );
+        assert_eq!(error("type = XX").position, 7);
+        assert_eq!(error("level in 13..").kind, QueryErrorKind::UnexpectedEnd);
+        assert_eq!(error("level in 13..").position, 13);
+        assert_eq!(error("floor >= 14 ; limit 3").position, 12);
+        assert_eq!(error("level = 13 sort speed").position, 16);
+    }
+
+    #[test]
+    fn query_records() {
+        let record = |acc: Option<f64>, max_combo: bool, level: u8| {
+            record(0, ChartType::Sc)
+                .with_acc(acc)
+                .with_max_combo(max_combo)
+                .with_level(level)
+        };
+        let records = vec![
+            record(None, false, 13),
+            record(Some(98.0), false, 14),
+            record(Some(99.5), true, 14),
+            record(Some(100.0), true, 15),
+        ];
+
+        let query = ChartQuery::parse("lamp >= mc sort acc desc").unwrap();
+        assert_eq!(
+            query,
+            ChartQuery::new()
+                .min_lamp(ClearLamp::MaxCombo)
+                .sort_by(SortKey::Accuracy, SortOrder::Desc)
+        );
+        let found = query.filter_records(&records);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].acc_rate, Some(100.0));
+
+        let found = ChartQuery::parse("level = 14 and lamp in (unplayed, played)")
+            .unwrap()
+            .filter_records(&records);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].acc_rate, Some(98.0));
+
+        assert_eq!(ChartQuery::parse("lamp = cleared").unwrap_err().position, 7);
+    }
+}
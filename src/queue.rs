@@ -0,0 +1,446 @@
+use crate::journal::Journal;
+use crate::register::check_record;
+use crate::{
+    APIError, AuthClient, ButtonMode, ChartType, RecordProblem, SongCatagory, UserChartRecord,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// A state of a queued record
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum QueueStatus {
+    /// Waiting for sending (again)
+    Pending,
+    /// Accepted by server. `update` is from `APIRegisterResult.update`.
+    Accepted { update: bool },
+    /// Rejected by server (or by checking before sending), with the reason
+    Rejected(String),
+}
+
+/// Errors on `SubmissionQueue::flush()`
+#[derive(Debug)]
+pub enum QueueError {
+    /// Failed to load the DLC list, or to send a record
+    APIError(APIError),
+    /// Failed to save the queue file
+    IOError(io::Error),
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::APIError(e) => write!(f, "API error: {}", e),
+            Self::IOError(e) => write!(f, "Queue file error: {}", e),
+        }
+    }
+}
+
+impl From<APIError> for QueueError {
+    fn from(error: APIError) -> Self {
+        Self::APIError(error)
+    }
+}
+
+impl From<io::Error> for QueueError {
+    fn from(error: io::Error) -> Self {
+        Self::IOError(error)
+    }
+}
+
+/// A record stored on `SubmissionQueue`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueuedRecord {
+    pub song_id: usize,
+    pub title: String,
+    /// A DLC code of the song (e. g.: `VE4`)
+    pub dlc_code: String,
+    pub button: u8,
+    /// A difficulty type of the chart (e. g.: `SC`)
+    pub pattern: String,
+    pub score: f64,
+    pub max_combo: bool,
+    pub status: QueueStatus,
+    /// Number of sending tries
+    pub attempts: u32,
+    /// An error on the last try
+    pub last_error: Option<String>,
+    pub queued_at: DateTime<Utc>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+}
+
+impl QueuedRecord {
+    fn from_record(record: &UserChartRecord) -> Self {
+        Self {
+            song_id: record.song_id,
+            title: record.title.clone(),
+            dlc_code: record
+                .song_cat
+                .as_ref()
+                .map(|c| c.to_string())
+                .unwrap_or_default(),
+            button: u8::from(record.button.clone()),
+            pattern: record.chart_type.to_string(),
+            score: record.acc_rate.unwrap_or(0.0),
+            max_combo: record.is_max_combo,
+            status: QueueStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            queued_at: Utc::now(),
+            last_attempt_at: None,
+        }
+    }
+
+    /// Convert to a record for sending
+    pub fn to_record(&self) -> UserChartRecord {
+        let mut record = UserChartRecord::new();
+        record.song_id = self.song_id;
+        record.title = self.title.clone();
+        record.button = ButtonMode::from(self.button);
+        record.chart_type = ChartType::from(self.pattern.as_str());
+        record.acc_rate = Some(self.score);
+        record.is_max_combo = self.max_combo;
+        if !self.dlc_code.is_empty() {
+            record.song_cat = Some(SongCatagory::from(self.dlc_code.as_str()));
+        }
+        record
+    }
+
+    /// Whether two records are for the same chart
+    fn is_same_chart(&self, other: &Self) -> bool {
+        let same_song = if self.song_id != 0 && other.song_id != 0 {
+            self.song_id == other.song_id
+        } else {
+            self.title == other.title && self.dlc_code == other.dlc_code
+        };
+        same_song && self.button == other.button && self.pattern == other.pattern
+    }
+
+    /// Whether it's better than other one (higher score first, MAX COMBO next)
+    fn is_better_than(&self, other: &Self) -> bool {
+        self.score > other.score
+            || (self.score == other.score && self.max_combo && !other.max_combo)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status != QueueStatus::Pending
+    }
+}
+
+/// A report of finished records on `SubmissionQueue`
+#[derive(Debug)]
+pub struct QueueReport {
+    pub accepted: Vec<QueuedRecord>,
+    pub rejected: Vec<QueuedRecord>,
+    /// Number of records still waiting
+    pub pending_count: usize,
+}
+
+/// A queue of records for `AuthClient::register_record()`, stored on a file.
+///
+/// Records are kept on the file until they are accepted or rejected by server, so it can be
+/// sent later (e. g. when internet is back). For the same chart, only the best pending record
+/// is kept, and a record not better than an accepted one is not queued.
+/// ## Example
+/// ```no_run
+/// # use std::time::Duration;
+/// # use v_archive_rs::{AuthClient, SubmissionQueue, UserChartRecord};
+/// #
+/// # fn example(client: AuthClient, record: UserChartRecord) {
+/// let mut queue = SubmissionQueue::open("pending_records.json").unwrap();
+/// queue.push(&record).unwrap();
+///
+/// // Later, when the internet is back
+/// if queue.flush(&client, Duration::from_millis(500)).is_ok() {
+///     let report = queue.report();
+///     println!("Accepted: {}", report.accepted.len());
+///     println!("Rejected: {}", report.rejected.len());
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SubmissionQueue {
+    path: PathBuf,
+    items: Vec<QueuedRecord>,
+    journal: Option<Journal>,
+}
+
+impl SubmissionQueue {
+    /// Open a queue file. If the file doesn't exist, it starts with an empty queue.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let items = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            path,
+            items,
+            journal: None,
+        })
+    }
+
+    /// Write every registration try on `flush()` to the journal (instead of the client's one)
+    pub fn set_journal(&mut self, journal: Journal) {
+        self.journal = Some(journal);
+    }
+
+    /// All records on the queue
+    pub fn items(&self) -> &[QueuedRecord] {
+        &self.items
+    }
+
+    /// Records still waiting for sending
+    pub fn pending(&self) -> impl Iterator<Item = &QueuedRecord> {
+        self.items.iter().filter(|i| !i.is_finished())
+    }
+
+    /// Add a record to the queue, and save it.
+    ///
+    /// If there's a pending record for the same chart, only the better one is kept.
+    /// If an accepted record for the same chart is as good or better, it's not queued.
+    pub fn push(&mut self, record: &UserChartRecord) -> io::Result<()> {
+        let new_item = QueuedRecord::from_record(record);
+
+        let is_accepted = self.items.iter().any(|i| {
+            matches!(i.status, QueueStatus::Accepted { .. })
+                && i.is_same_chart(&new_item)
+                && !new_item.is_better_than(i)
+        });
+        if is_accepted {
+            return Ok(());
+        }
+
+        let same_chart = self
+            .items
+            .iter()
+            .position(|i| !i.is_finished() && i.is_same_chart(&new_item));
+
+        match same_chart {
+            Some(index) => {
+                if new_item.is_better_than(&self.items[index]) {
+                    self.items[index] = new_item;
+                }
+            }
+            None => self.items.push(new_item),
+        }
+
+        self.save()
+    }
+
+    /// Send pending records to server, with `interval` between each request.
+    ///
+    /// It stops on a network error (the rest stay pending), and saves the queue after each record.
+    /// It returns `Err` if the DLC list cannot be loaded, on the network error, or if the queue
+    /// file (or the journal) cannot be written. A record is not sent while the journal cannot be
+    /// written, and stays pending.
+    pub fn flush(&mut self, client: &AuthClient, interval: Duration) -> Result<(), QueueError> {
+        let journal = self.journal.as_ref().or(client.journal());
+        if let Some(journal) = journal {
+            journal.check_writable()?;
+        }
+        let dlcs = client.all_dlcs()?;
+        let mut is_first_request = true;
+
+        for index in 0..self.items.len() {
+            if self.items[index].is_finished() {
+                continue;
+            }
+
+            let record = self.items[index].to_record();
+            let dlc_name = match check_record(&record, &dlcs, None) {
+                Ok(n) => n,
+                Err(problems) => {
+                    let messages: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+                    self.items[index].status = QueueStatus::Rejected(messages.join(", "));
+                    self.save()?;
+                    continue;
+                }
+            };
+
+            if !is_first_request {
+                thread::sleep(interval);
+            }
+            is_first_request = false;
+
+            let (result, journal_error) =
+                client.send_checked_record(&record, &dlc_name, journal)?;
+
+            let item = &mut self.items[index];
+            item.attempts += 1;
+            item.last_attempt_at = Some(Utc::now());
+
+            let stop_error = match result {
+                Ok(r) if !r.success => {
+                    item.status = QueueStatus::Rejected(RecordProblem::NotAccepted.to_string());
+                    None
+                }
+                Ok(r) => {
+                    item.status = QueueStatus::Accepted { update: r.update };
+                    None
+                }
+                Err(e) if is_temporary_error(&e) => {
+                    item.last_error = Some(e.to_string());
+                    Some(e)
+                }
+                Err(e) => {
+                    item.status = QueueStatus::Rejected(e.to_string());
+                    None
+                }
+            };
+
+            self.save()?;
+
+            if let Some(e) = stop_error {
+                return Err(QueueError::APIError(e));
+            }
+            if let Some(e) = journal_error {
+                return Err(QueueError::IOError(e));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A report of accepted and rejected records
+    pub fn report(&self) -> QueueReport {
+        let mut report = QueueReport {
+            accepted: Vec::new(),
+            rejected: Vec::new(),
+            pending_count: 0,
+        };
+
+        for item in &self.items {
+            match item.status {
+                QueueStatus::Pending => report.pending_count += 1,
+                QueueStatus::Accepted { .. } => report.accepted.push(item.clone()),
+                QueueStatus::Rejected(_) => report.rejected.push(item.clone()),
+            }
+        }
+
+        report
+    }
+
+    /// Remove accepted and rejected records from the queue, and save it
+    pub fn clear_finished(&mut self) -> io::Result<()> {
+        self.items.retain(|i| !i.is_finished());
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let text = serde_json::to_string_pretty(&self.items)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        // Write on a temporary file first, not to break the queue file
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        fs::write(&temp_path, text)?;
+        fs::rename(&temp_path, &self.path)
+    }
+}
+
+/// Errors worth to try again later
+fn is_temporary_error(error: &APIError) -> bool {
+    matches!(
+        error,
+        APIError::UnknownError | APIError::InernalServerError | APIError::HTTPErr(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Client, UserToken};
+
+    #[test]
+    fn queue_keeps_best_and_persists() {
+        let path =
+            std::env::temp_dir().join(format!("v_archive_rs_queue_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut record = UserChartRecord::new();
+        record.song_id = 555;
+        record.title = "Gloxinia".to_string();
+        record.button = ButtonMode::Six;
+        record.chart_type = ChartType::Sc;
+        record.acc_rate = Some(98.1);
+        record.song_cat = Some(SongCatagory::from("VE4"));
+
+        let mut queue = SubmissionQueue::open(&path).unwrap();
+        queue.push(&record).unwrap();
+
+        record.acc_rate = Some(99.3);
+        queue.push(&record).unwrap();
+
+        record.acc_rate = Some(97.0);
+        queue.push(&record).unwrap();
+
+        record.chart_type = ChartType::Maximum;
+        queue.push(&record).unwrap();
+
+        let mut queue = SubmissionQueue::open(&path).unwrap();
+        assert_eq!(queue.items().len(), 2);
+        assert_eq!(queue.items()[0].score, 99.3);
+        assert_eq!(queue.report().pending_count, 2);
+
+        // A worse record for an accepted chart is not queued again
+        queue.items[0].status = QueueStatus::Accepted { update: false };
+        record.chart_type = ChartType::Sc;
+        record.acc_rate = Some(99.0);
+        queue.push(&record).unwrap();
+        assert_eq!(queue.items().len(), 2);
+
+        record.acc_rate = Some(99.5);
+        queue.push(&record).unwrap();
+        assert_eq!(queue.items().len(), 3);
+        assert_eq!(queue.report().pending_count, 2);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unwritable_journal_keeps_pending() {
+        let path = std::env::temp_dir().join(format!(
+            "v_archive_rs_queue_journal_{}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let mut record = UserChartRecord::new();
+        record.song_id = 555;
+        record.title = "Gloxinia".to_string();
+        record.button = ButtonMode::Six;
+        record.chart_type = ChartType::Sc;
+        record.acc_rate = Some(98.1);
+        record.song_cat = Some(SongCatagory::from("VE3"));
+
+        let mut queue = SubmissionQueue::open(&path).unwrap();
+        queue.push(&record).unwrap();
+        queue.set_journal(Journal::open(
+            std::env::temp_dir()
+                .join("v_archive_rs_missing_dir")
+                .join("journal.jsonl"),
+        ));
+
+        let token = UserToken::new(1, "95d6c422-52b4-4016-8587-38c46a2e7917".to_string()).unwrap();
+        let client = Client::new().authenticate(token);
+
+        match queue.flush(&client, Duration::ZERO) {
+            Err(QueueError::IOError(_)) => {}
+            r => panic!("it should be an I/O error: {:?}", r),
+        }
+        assert_eq!(queue.items()[0].status, QueueStatus::Pending);
+        assert_eq!(queue.items()[0].attempts, 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
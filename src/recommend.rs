@@ -0,0 +1,367 @@
+use crate::{ButtonMode, Catalog, ChartRef, OwnedContent, UserChartRecord, UserFloorRecordBoard};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Weights for each kind of recommendation. A weight of `0.0` turns the kind off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecommendWeights {
+    pub unplayed: f64,
+    pub low_accuracy: f64,
+    pub near_max_combo: f64,
+}
+
+impl Default for RecommendWeights {
+    fn default() -> Self {
+        Self {
+            unplayed: 1.0,
+            low_accuracy: 1.0,
+            near_max_combo: 1.0,
+        }
+    }
+}
+
+/// Why a chart is recommended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecommendKind {
+    /// Not played, around the comfortable floor
+    Unplayed,
+    /// Played, with lower accuracy than other charts on the floor
+    LowAccuracy,
+    /// Played with high accuracy, but without max combo
+    NearMaxCombo,
+}
+
+impl fmt::Display for RecommendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unplayed => write!(f, "UNPLAYED"),
+            Self::LowAccuracy => write!(f, "LOW ACCURACY"),
+            Self::NearMaxCombo => write!(f, "NEAR MAX COMBO"),
+        }
+    }
+}
+
+/// A chart to play next
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommendation {
+    pub chart: ChartRef,
+    pub title: String,
+    pub floor: Option<f64>,
+    /// The user's accuracy (`None` for an unplayed chart)
+    pub acc_rate: Option<f64>,
+    pub kind: RecommendKind,
+    /// Weighted score; a higher one is recommended first
+    pub score: f64,
+    /// A reason for people (e. g. `Unplayed, floor 14.2 is near your comfortable floor 14.0 on 6B`)
+    pub reason: String,
+}
+
+/// Recommends charts to play next, from a user's floor boards.
+///
+/// There are three kinds of recommendations:
+/// * Unplayed charts within `floor_range` of the comfortable floor (the median floor of records
+///   with `comfort_acc` or more), for each button mode
+/// * Played charts with accuracy lower than the average of other charts on the same floor
+/// * Played charts with `max_combo_acc` or more, but without max combo
+///
+/// Each kind gets a score from `0.0` to `1.0`, and it's multiplied by the weight.
+/// If a chart is in several kinds, the best one is used.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{load_user_floor_board, Catalog, OwnedContent, Recommender};
+/// #
+/// # fn main() {
+/// let catalog = Catalog::load().unwrap();
+/// let board = load_user_floor_board("내꺼", 6, "SC").unwrap();
+///
+/// let mut recommender = Recommender::new();
+/// recommender.owned = Some(OwnedContent::load("owned.toml").unwrap());
+/// recommender.count = 10;
+///
+/// for r in recommender.recommend(&[board], &catalog) {
+///     println!("{} ({}): {}", r.title, r.chart, r.reason);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommender {
+    pub weights: RecommendWeights,
+    /// Only charts of owned songs (if given)
+    pub owned: Option<OwnedContent>,
+    /// Maximum numbers of recommendations
+    pub count: usize,
+    /// Floors from the comfortable floor, for unplayed charts
+    pub floor_range: f64,
+    /// Accuracy for the comfortable floor
+    pub comfort_acc: f64,
+    /// Minimum accuracy for near max combo charts
+    pub max_combo_acc: f64,
+}
+
+impl Recommender {
+    /// A recommender with default weights: 20 recommendations, floor range `0.5`,
+    /// comfortable accuracy `98.0` and near max combo accuracy `99.0`.
+    pub fn new() -> Self {
+        Self {
+            weights: RecommendWeights::default(),
+            owned: None,
+            count: 20,
+            floor_range: 0.5,
+            comfort_acc: 98.0,
+            max_combo_acc: 99.0,
+        }
+    }
+
+    /// The comfortable floor for a button mode: the median floor of records
+    /// with `comfort_acc` or more (`None` if there's no such record)
+    pub fn comfortable_floor(
+        &self,
+        boards: &[UserFloorRecordBoard],
+        button: &ButtonMode,
+    ) -> Option<f64> {
+        let records = self.records(boards);
+        comfortable_floor(records.values().copied(), button, self.comfort_acc)
+    }
+
+    pub fn recommend(
+        &self,
+        boards: &[UserFloorRecordBoard],
+        catalog: &Catalog,
+    ) -> Vec<Recommendation> {
+        let records = self.records(boards);
+        let mut best: HashMap<ChartRef, Recommendation> = HashMap::new();
+        let mut push = |r: Recommendation| {
+            if r.score <= 0.0 {
+                return;
+            }
+            match best.get(&r.chart) {
+                Some(old) if old.score >= r.score => {}
+                _ => {
+                    best.insert(r.chart.clone(), r);
+                }
+            }
+        };
+
+        let title_of = |record: &UserChartRecord| match catalog.song(record.song_id) {
+            Some(song) if record.title.is_empty() => song.content.title.clone(),
+            _ => record.title.clone(),
+        };
+
+        // Unplayed charts around the comfortable floor
+        if self.weights.unplayed > 0.0 && self.floor_range > 0.0 {
+            let mut buttons: Vec<&ButtonMode> = boards.iter().map(|b| &b.button).collect();
+            buttons.sort();
+            buttons.dedup();
+            for button in buttons {
+                let comfort =
+                    match comfortable_floor(records.values().copied(), button, self.comfort_acc) {
+                        Some(f) => f,
+                        None => continue,
+                    };
+
+                for (song, chart) in
+                    catalog.charts_by_floor(comfort - self.floor_range, comfort + self.floor_range)
+                {
+                    let chart_ref = ChartRef::of(song, chart);
+                    let played = records
+                        .get(&chart_ref)
+                        .is_some_and(|r| r.acc_rate.is_some());
+                    let owned = self.owned.as_ref().is_none_or(|o| o.owns_song(song));
+                    if &chart.button != button || played || !owned {
+                        continue;
+                    }
+
+                    let floor = chart.floor.unwrap_or(comfort);
+                    let closeness = 1.0 - (floor - comfort).abs() / self.floor_range;
+                    push(Recommendation {
+                        title: song.title.clone(),
+                        floor: chart.floor,
+                        acc_rate: None,
+                        kind: RecommendKind::Unplayed,
+                        score: closeness.max(0.01) * self.weights.unplayed,
+                        reason: format!(
+                            "Unplayed, floor {:.1} is near your comfortable floor {:.1} on {}B",
+                            floor, comfort, button
+                        ),
+                        chart: chart_ref,
+                    });
+                }
+            }
+        }
+
+        // Played charts with lower accuracy than other charts on the floor
+        if self.weights.low_accuracy > 0.0 {
+            let mut peers: HashMap<(ButtonMode, u64), (f64, usize)> = HashMap::new();
+            for r in records.values() {
+                if let (Some(acc), Some(floor)) = (r.acc_rate, r.floor_level) {
+                    let peer = peers
+                        .entry((r.button.clone(), floor.to_bits()))
+                        .or_insert((0.0, 0));
+                    peer.0 += acc;
+                    peer.1 += 1;
+                }
+            }
+
+            let gaps: Vec<(&UserChartRecord, f64, f64)> = records
+                .values()
+                .filter_map(|r| {
+                    let (acc, floor) = (r.acc_rate?, r.floor_level?);
+                    let (sum, n) = peers[&(r.button.clone(), floor.to_bits())];
+                    if n < 2 {
+                        return None;
+                    }
+                    // The average of other charts on the floor
+                    let mean = (sum - acc) / (n - 1) as f64;
+                    Some((*r, mean, mean - acc)).filter(|(_, _, gap)| *gap > 0.0)
+                })
+                .collect();
+            let max_gap = gaps.iter().map(|(_, _, g)| *g).fold(0.0, f64::max);
+
+            for (r, mean, gap) in gaps {
+                push(Recommendation {
+                    chart: r.chart_ref(),
+                    title: title_of(r),
+                    floor: r.floor_level,
+                    acc_rate: r.acc_rate,
+                    kind: RecommendKind::LowAccuracy,
+                    score: gap / max_gap * self.weights.low_accuracy,
+                    reason: format!(
+                        "{:.2}% is {:.2}% below the average on floor {:.1} ({:.2}%)",
+                        r.acc_rate.unwrap_or(0.0),
+                        gap,
+                        r.floor_level.unwrap_or(0.0),
+                        mean
+                    ),
+                });
+            }
+        }
+
+        // Played charts with high accuracy, but without max combo
+        if self.weights.near_max_combo > 0.0 && self.max_combo_acc < 100.0 {
+            for r in records.values().filter(|r| !r.is_max_combo) {
+                let acc = match r.acc_rate {
+                    Some(a) if a >= self.max_combo_acc => a,
+                    _ => continue,
+                };
+                let closeness = (acc - self.max_combo_acc) / (100.0 - self.max_combo_acc);
+                push(Recommendation {
+                    chart: r.chart_ref(),
+                    title: title_of(r),
+                    floor: r.floor_level,
+                    acc_rate: r.acc_rate,
+                    kind: RecommendKind::NearMaxCombo,
+                    score: closeness.clamp(0.01, 1.0) * self.weights.near_max_combo,
+                    reason: format!("{:.2}% without max combo", acc),
+                });
+            }
+        }
+
+        let mut recommendations: Vec<Recommendation> = best.into_values().collect();
+        recommendations.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.chart.cmp(&b.chart)));
+        recommendations.truncate(self.count);
+        recommendations
+    }
+
+    /// Owned records on boards, one for each chart
+    fn records<'a>(
+        &self,
+        boards: &'a [UserFloorRecordBoard],
+    ) -> HashMap<ChartRef, &'a UserChartRecord> {
+        let mut records = HashMap::new();
+        for r in boards
+            .iter()
+            .flat_map(|b| b.floors.iter())
+            .flat_map(|f| f.records.iter())
+            .filter(|r| self.owned.as_ref().is_none_or(|o| o.owns_record(r)))
+        {
+            records.entry(r.chart_ref()).or_insert(r);
+        }
+        records
+    }
+}
+
+impl Default for Recommender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn comfortable_floor<'a, I>(records: I, button: &ButtonMode, comfort_acc: f64) -> Option<f64>
+where
+    I: IntoIterator<Item = &'a UserChartRecord>,
+{
+    let mut floors: Vec<f64> = records
+        .into_iter()
+        .filter(|r| &r.button == button && r.acc_rate.is_some_and(|a| a >= comfort_acc))
+        .filter_map(|r| r.floor_level)
+        .collect();
+    if floors.is_empty() {
+        return None;
+    }
+
+    floors.sort_by(|a, b| a.total_cmp(b));
+    // The median (the middle one, or the average of two middle ones)
+    let n = floors.len();
+    Some((floors[(n - 1) / 2] + floors[n / 2]) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::songs;
+    use crate::{user_floor_board_parse, SongCatagory};
+
+    const BOARD_JSON: &str = r#"{
+        "success": true, "board": "SC", "button": "6", "totalCount": 4,
+        "floors": [
+            {"floorNumber": 13.0, "patterns": [
+                {"title": 11, "name": "A", "composer": "a", "pattern": "SC", "score": "99.60", "maxCombo": 0, "djpower": 10.0, "rating": 200.0, "dlc": "R", "dlcCode": "R", "updatedAt": null},
+                {"title": 12, "name": "B", "composer": "b", "pattern": "SC", "score": "99.00", "maxCombo": 1, "djpower": 9.0, "rating": 180.0, "dlc": "R", "dlcCode": "R", "updatedAt": null},
+                {"title": 13, "name": "C", "composer": "c", "pattern": "SC", "score": "95.00", "maxCombo": 0, "djpower": 9.0, "rating": 170.0, "dlc": "R", "dlcCode": "R", "updatedAt": null}
+            ]},
+            {"floorNumber": 13.2, "patterns": [
+                {"title": 14, "name": "D", "composer": "d", "pattern": "SC", "score": null, "maxCombo": 0, "djpower": 0.0, "rating": 0.0, "dlc": "R", "dlcCode": "R", "updatedAt": null}
+            ]}
+        ]
+    }"#;
+
+    #[test]
+    fn recommendations() {
+        let catalog = Catalog::new(songs());
+        let boards = vec![user_floor_board_parse(BOARD_JSON.to_string())];
+
+        let mut recommender = Recommender::new();
+        assert_eq!(
+            recommender.comfortable_floor(&boards, &ButtonMode::Six),
+            Some(13.0)
+        );
+
+        let recommendations = recommender.recommend(&boards, &catalog);
+        let find = |id: usize| recommendations.iter().find(|r| r.chart.song_id == id);
+        assert_eq!(recommendations.len(), 5);
+        assert_eq!(find(13).unwrap().kind, RecommendKind::LowAccuracy);
+        assert_eq!(find(13).unwrap().score, 1.0);
+        assert_eq!(find(11).unwrap().kind, RecommendKind::NearMaxCombo);
+        assert_eq!(find(11).unwrap().reason, "99.60% without max combo");
+        assert_eq!(find(14).unwrap().kind, RecommendKind::Unplayed);
+        assert_eq!(find(15).unwrap().kind, RecommendKind::Unplayed);
+        // Floor 13.5 is on the edge of the range
+        assert_eq!(find(1).unwrap().score, 0.01);
+        // Floor 15.0 is far from the comfortable floor
+        assert!(find(16).is_none());
+
+        recommender.owned = Some(OwnedContent::new());
+        recommender.weights.low_accuracy = 0.0;
+        recommender.count = 2;
+        let recommendations = recommender.recommend(&boards, &catalog);
+        let kinds: Vec<RecommendKind> = recommendations.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds.len(), 2);
+        assert!(!kinds.contains(&RecommendKind::LowAccuracy));
+        assert!(recommendations.iter().all(|r| catalog
+            .song(r.chart.song_id)
+            .unwrap()
+            .content
+            .song_cat
+            != SongCatagory::from("VE")));
+    }
+}
@@ -0,0 +1,592 @@
+use crate::alias::apply_alias;
+use crate::journal::{Journal, JournalEntry};
+use crate::validate::{record_problems, song_problems};
+use crate::{
+    all_dlcs, all_songs, catch_server_err, load_user_song_result, APIError, APIRegisterResult,
+    AliasTable, Dlc, RecordProblem, SongSet, UserChartRecord, UserToken,
+};
+use chrono::Utc;
+use serde::Serialize;
+use std::io;
+use std::thread;
+use std::time::Duration;
+use ureq::Error;
+
+/// An outcome of registering a record
+#[derive(Debug)]
+pub enum RegisterOutcome {
+    /// The record is registered as a new one
+    Inserted,
+    /// The record updated an existing one (`APIRegisterResult.update`)
+    Updated,
+    /// The record is rejected by checking before sending, or by server
+    Rejected(Vec<RecordProblem>),
+    /// The record is not sent, because it would not improve one on server
+    Skipped(String),
+    /// Failed to send the record
+    Failed(APIError),
+}
+
+/// A record with its outcome on `AuthClient::register_records()`
+#[derive(Debug)]
+pub struct RegisterEntry {
+    pub record: UserChartRecord,
+    pub outcome: RegisterOutcome,
+    /// The record on server before registering (only on safe mode, and if the chart was played)
+    pub previous: Option<UserChartRecord>,
+    /// An error on writing the journal after sending (the outcome is still from server)
+    pub journal_error: Option<String>,
+}
+
+/// A report from `AuthClient::register_records()`, in the same order as given records
+#[derive(Debug)]
+pub struct RegisterReport {
+    pub entries: Vec<RegisterEntry>,
+}
+
+impl RegisterReport {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Whether every record is inserted or updated
+    pub fn is_all_registered(&self) -> bool {
+        self.entries.iter().all(|e| {
+            matches!(
+                e.outcome,
+                RegisterOutcome::Inserted | RegisterOutcome::Updated
+            )
+        })
+    }
+}
+
+impl Default for RegisterReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Options for `AuthClient::register_records()`
+#[derive(Debug, Clone)]
+pub struct RegisterOptions<'a> {
+    /// A delay between each request
+    pub interval: Duration,
+    /// A song list (from `all_songs()`) for checking records with `validate_record()`
+    pub songs: Option<&'a [SongSet]>,
+    /// A username for safe mode. If it's given, records which would not improve one on server
+    /// are skipped (see `AuthClient::safe_register_record()`).
+    pub safe_username: Option<&'a str>,
+    /// A journal for writing every registration try (records rejected before sending are not written)
+    pub journal: Option<&'a Journal>,
+    /// Aliases for titles. A title which is an alias is changed to the real title before checking
+    /// and sending (the song list is loaded if `songs` is not given).
+    pub aliases: Option<&'a AliasTable>,
+}
+
+impl RegisterOptions<'_> {
+    pub fn new() -> Self {
+        Self {
+            interval: Duration::from_millis(500),
+            songs: None,
+            safe_username: None,
+            journal: None,
+            aliases: None,
+        }
+    }
+}
+
+impl Default for RegisterOptions<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check a record before sending. It returns the full DLC name for the record.
+pub(crate) fn check_record(
+    record: &UserChartRecord,
+    dlcs: &[Dlc],
+    songs: Option<&[SongSet]>,
+) -> Result<String, Vec<RecordProblem>> {
+    let mut problems = record_problems(record);
+
+    if let Some(songs) = songs {
+        problems.append(&mut song_problems(record, songs));
+    }
+
+    let dlc_name = match &record.song_cat {
+        None => {
+            problems.push(RecordProblem::MissingCategory);
+            None
+        }
+        Some(c) => {
+            let dlc_code = c.to_string();
+            let found = dlcs.iter().find(|d| d.dlc_code == dlc_code);
+            if found.is_none() {
+                problems.push(RecordProblem::UnknownDlc(dlc_code));
+            }
+            found.map(|d| d.dlc_name.clone())
+        }
+    };
+
+    match dlc_name {
+        Some(n) if problems.is_empty() => Ok(n),
+        _ => Err(problems),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct APIPlayRecord<'a> {
+    name: &'a str,
+    dlc: &'a str,
+    button: u8,
+    pattern: String,
+    score: f64,
+    max_combo: u8,
+}
+
+impl<'a> APIPlayRecord<'a> {
+    fn new(record: &'a UserChartRecord, dlc_name: &'a str) -> Self {
+        Self {
+            name: &record.title,
+            dlc: dlc_name,
+            button: u8::from(record.button.clone()),
+            pattern: record.chart_type.to_string(),
+            score: record.acc_rate.unwrap_or(0.0),
+            max_combo: record.is_max_combo as u8,
+        }
+    }
+}
+
+/// A record with the real title if its title is an alias.
+/// The song list is loaded if it's not given (only with aliases).
+fn aliased_record(
+    record: &UserChartRecord,
+    aliases: Option<&AliasTable>,
+    songs: Option<&[SongSet]>,
+) -> Result<UserChartRecord, APIError> {
+    let mut record = record.clone();
+    if let Some(aliases) = aliases {
+        match songs {
+            Some(songs) => apply_alias(&mut record, songs, aliases),
+            None => apply_alias(&mut record, &all_songs()?, aliases),
+        }
+    }
+    Ok(record)
+}
+
+/// A result of a sent record, with an error on writing the journal after sending
+pub(crate) type SentRecord = (Result<APIRegisterResult, APIError>, Option<io::Error>);
+
+/// Send a checked record to server, and write it on the journal if given.
+///
+/// If the journal cannot be written, the record is not sent and it returns `Err`.
+/// An error on writing the journal after sending is given separately, with the result.
+pub(crate) fn send_record(
+    token: &UserToken,
+    record: &UserChartRecord,
+    dlc_name: &str,
+    journal: Option<&Journal>,
+) -> io::Result<SentRecord> {
+    if let Some(journal) = journal {
+        journal.check_writable()?;
+    }
+    let user_num = &token.user_num;
+    let data_for_api = APIPlayRecord::new(record, dlc_name);
+
+    let record_serial = serde_json::to_string(&data_for_api).unwrap();
+    let sent_at = Utc::now();
+
+    let post_url = format!("https://v-archive.net/client/open/{user_num}/score");
+    let resp = ureq::post(&post_url)
+        .set("Authorization", token.secret())
+        .set("Content-Type", "application/json")
+        .send_string(&record_serial);
+
+    let (status, result) = match resp {
+        Ok(resp) => {
+            let status = resp.status();
+            let result = resp
+                .into_string()
+                .ok()
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .ok_or(APIError::UnknownError);
+            (Some(status), result)
+        }
+        Err(Error::Status(code, resp)) => (Some(code), Err(catch_server_err(code, resp))),
+        Err(_) => (None, Err(APIError::UnknownError)),
+    };
+
+    let journal_error = journal.and_then(|journal| {
+        journal
+            .append(&JournalEntry {
+                timestamp: sent_at,
+                user_num: token.user_num,
+                payload: serde_json::to_value(&data_for_api).unwrap(),
+                status,
+                result: result.as_ref().ok().cloned(),
+                error: result.as_ref().err().map(|e| e.to_string()),
+            })
+            .err()
+    });
+
+    Ok((result, journal_error))
+}
+
+/// Register a user's record for a chart.
+///
+/// If the record has problems (e. g. no `song_cat`), it returns `APIError::WrongParameter`.
+/// If the journal cannot be written, the record is not sent (`APIError::JournalError`).
+/// An error on writing the journal after sending is given with the result.
+pub(crate) fn register_record(
+    token: &UserToken,
+    record: &UserChartRecord,
+    journal: Option<&Journal>,
+    aliases: Option<&AliasTable>,
+) -> SentRecord {
+    let record = match aliased_record(record, aliases, None) {
+        Ok(r) => r,
+        Err(e) => return (Err(e), None),
+    };
+    let dlcs = match all_dlcs() {
+        Ok(d) => d,
+        Err(e) => return (Err(e), None),
+    };
+
+    match check_record(&record, &dlcs, None) {
+        Ok(dlc_name) => send_record(token, &record, &dlc_name, journal)
+            .unwrap_or_else(|e| (Err(APIError::JournalError(e.to_string())), None)),
+        Err(problems) => {
+            let messages: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+            (Err(APIError::WrongParameter(messages.join(", "))), None)
+        }
+    }
+}
+
+/// An outcome of `AuthClient::safe_register_record()`
+#[derive(Debug)]
+pub enum SafeRegisterOutcome {
+    Registered(APIRegisterResult),
+    /// The record is not sent, with the reason
+    Skipped(String),
+}
+
+/// A result of `AuthClient::safe_register_record()`
+#[derive(Debug)]
+pub struct SafeRegisterResult {
+    /// The record on server before registering (`None` if the chart was not played)
+    pub previous: Option<UserChartRecord>,
+    pub outcome: SafeRegisterOutcome,
+    /// An error on writing the journal after sending (the record was sent anyway)
+    pub journal_error: Option<String>,
+}
+
+/// Check whether `record` improves `previous` without making anything worse.
+/// It returns the reason if not.
+pub(crate) fn check_improvement(
+    record: &UserChartRecord,
+    previous: Option<&UserChartRecord>,
+) -> Result<(), String> {
+    let previous = match previous {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+    let prev_acc = previous.acc_rate.unwrap_or(0.0);
+    let new_acc = record.acc_rate.unwrap_or(0.0);
+
+    if new_acc < prev_acc {
+        return Err(format!(
+            "Accuracy rate {} is lower than {} on server",
+            new_acc, prev_acc
+        ));
+    }
+    if previous.is_max_combo && !record.is_max_combo {
+        return Err("MAX COMBO on server would be lost".to_string());
+    }
+    if new_acc == prev_acc && previous.is_max_combo == record.is_max_combo {
+        return Err(format!(
+            "Same as on server: {} ({})",
+            prev_acc,
+            if previous.is_max_combo {
+                "MAX COMBO"
+            } else {
+                "no MAX COMBO"
+            }
+        ));
+    }
+
+    Ok(())
+}
+
+/// Find a song ID of a record. If it's `0`, it looks up the title on `songs`.
+fn song_id_of(record: &UserChartRecord, songs: Option<&[SongSet]>) -> Option<usize> {
+    if record.song_id != 0 {
+        return Some(record.song_id);
+    }
+
+    let mut found = songs?.iter().filter(|s| {
+        s.content.title == record.title
+            && record
+                .song_cat
+                .as_ref()
+                .is_none_or(|c| c == &s.content.song_cat)
+    });
+
+    match (found.next(), found.next()) {
+        (Some(s), None) => Some(s.content.song_id),
+        _ => None,
+    }
+}
+
+/// Load a user's current record on server for the same chart of `record`
+fn load_previous_record(
+    username: &str,
+    record: &UserChartRecord,
+    songs: Option<&[SongSet]>,
+) -> Result<Option<UserChartRecord>, APIError> {
+    let song_id = match song_id_of(record, songs) {
+        Some(id) => id,
+        None => {
+            return Err(APIError::WrongParameter(format!(
+                "Cannot find song ID for \"{}\"",
+                record.title
+            )))
+        }
+    };
+
+    let song_result = match load_user_song_result(username, song_id) {
+        Ok(r) => r,
+        Err(APIError::CannotFoundChart) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    Ok(song_result.records.into_iter().find(|r| {
+        r.button == record.button && r.chart_type == record.chart_type && r.acc_rate.is_some()
+    }))
+}
+
+/// Register a user's record only if it improves one on server.
+///
+/// It loads the current record with `load_user_song_result()` first, and skips (with the reason)
+/// a record with lower accuracy rate, losing MAX COMBO, or nothing new.
+/// `record.song_id` should be set. If the journal cannot be written, the record is not sent
+/// (`APIError::JournalError`).
+pub(crate) fn safe_register_record(
+    token: &UserToken,
+    username: &str,
+    record: &UserChartRecord,
+    journal: Option<&Journal>,
+    aliases: Option<&AliasTable>,
+) -> Result<SafeRegisterResult, APIError> {
+    let record = &aliased_record(record, aliases, None)?;
+    let dlcs = all_dlcs()?;
+    let dlc_name = match check_record(record, &dlcs, None) {
+        Ok(n) => n,
+        Err(problems) => {
+            let messages: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+            return Err(APIError::WrongParameter(messages.join(", ")));
+        }
+    };
+
+    let previous = load_previous_record(username, record, None)?;
+
+    if let Err(reason) = check_improvement(record, previous.as_ref()) {
+        return Ok(SafeRegisterResult {
+            previous,
+            outcome: SafeRegisterOutcome::Skipped(reason),
+            journal_error: None,
+        });
+    }
+
+    let (result, journal_error) = send_record(token, record, &dlc_name, journal)
+        .map_err(|e| APIError::JournalError(e.to_string()))?;
+
+    Ok(SafeRegisterResult {
+        previous,
+        outcome: SafeRegisterOutcome::Registered(result?),
+        journal_error: journal_error.map(|e| e.to_string()),
+    })
+}
+
+/// Register a bunch of user's records.
+///
+/// Every record is checked before sending anything (also with `validate_record()` if
+/// `options.songs` is given), and records are sent with `options.interval` between each request.
+/// With `options.safe_username`, records are checked with current ones on server like
+/// `AuthClient::safe_register_record()`. It returns `Err` only if the DLC list (or the song list
+/// for `options.aliases`) cannot be loaded.
+pub(crate) fn register_records<I>(
+    token: &UserToken,
+    records: I,
+    options: &RegisterOptions,
+) -> Result<RegisterReport, APIError>
+where
+    I: IntoIterator<Item = UserChartRecord>,
+{
+    let dlcs = all_dlcs()?;
+    let loaded_songs = match (options.aliases, options.songs) {
+        (Some(_), None) => Some(all_songs()?),
+        _ => None,
+    };
+    let alias_songs = options.songs.or(loaded_songs.as_deref());
+
+    let checked: Vec<(UserChartRecord, Result<String, Vec<RecordProblem>>)> = records
+        .into_iter()
+        .map(|r| {
+            let r = aliased_record(&r, options.aliases, alias_songs)?;
+            let check = check_record(&r, &dlcs, options.songs);
+            Ok((r, check))
+        })
+        .collect::<Result<_, APIError>>()?;
+
+    let mut report = RegisterReport::new();
+    let mut is_first_request = true;
+    let mut wait = || {
+        if !is_first_request {
+            thread::sleep(options.interval);
+        }
+        is_first_request = false;
+    };
+
+    for (record, check) in checked {
+        let dlc_name = match check {
+            Ok(n) => n,
+            Err(problems) => {
+                report.entries.push(RegisterEntry {
+                    record,
+                    outcome: RegisterOutcome::Rejected(problems),
+                    previous: None,
+                    journal_error: None,
+                });
+                continue;
+            }
+        };
+
+        let mut previous = None;
+        if let Some(username) = options.safe_username {
+            wait();
+            match load_previous_record(username, &record, options.songs) {
+                Ok(p) => previous = p,
+                Err(e) => {
+                    report.entries.push(RegisterEntry {
+                        record,
+                        outcome: RegisterOutcome::Failed(e),
+                        previous: None,
+                        journal_error: None,
+                    });
+                    continue;
+                }
+            }
+
+            if let Err(reason) = check_improvement(&record, previous.as_ref()) {
+                report.entries.push(RegisterEntry {
+                    record,
+                    outcome: RegisterOutcome::Skipped(reason),
+                    previous,
+                    journal_error: None,
+                });
+                continue;
+            }
+        }
+
+        wait();
+        let (result, journal_error) = match send_record(token, &record, &dlc_name, options.journal)
+        {
+            Ok(sent) => sent,
+            Err(e) => (Err(APIError::JournalError(e.to_string())), None),
+        };
+        let outcome = match result {
+            Ok(r) if !r.success => RegisterOutcome::Rejected(vec![RecordProblem::NotAccepted]),
+            Ok(r) if r.update => RegisterOutcome::Updated,
+            Ok(_) => RegisterOutcome::Inserted,
+            Err(e) => RegisterOutcome::Failed(e),
+        };
+
+        report.entries.push(RegisterEntry {
+            record,
+            outcome,
+            previous,
+            journal_error: journal_error.map(|e| e.to_string()),
+        });
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{record, songs};
+    use crate::{ChartType, LegacyExtCat, SongCatagory};
+
+    fn example_dlcs() -> Vec<Dlc> {
+        vec![Dlc {
+            dlc_code: "ES".to_string(),
+            dlc_name: "EMOTIONAL S.".to_string(),
+            release_date: "2020-03-12".to_string(),
+        }]
+    }
+
+    #[test]
+    fn check_record_problems() {
+        let mut record = record(0, ChartType::Sc)
+            .with_title("Urban Night")
+            .with_acc(90.9);
+        record.song_cat = Some(SongCatagory::LegacyExtention(LegacyExtCat::EmotionalSense));
+
+        match check_record(&record, &example_dlcs(), None) {
+            Ok(name) => assert_eq!(name, "EMOTIONAL S.".to_string()),
+            Err(p) => panic!("it should be passed: {:?}", p),
+        };
+
+        record.acc_rate = Some(100.1);
+        record.song_cat = Some(SongCatagory::from("VE"));
+
+        match check_record(&record, &example_dlcs(), None) {
+            Ok(_) => panic!("this should be become to error."),
+            Err(p) => {
+                assert_eq!(p.len(), 2);
+                assert!(matches!(p[0], RecordProblem::AccuracyOutOfRange(_)));
+                assert!(matches!(p[1], RecordProblem::UnknownDlc(_)));
+            }
+        };
+    }
+
+    #[test]
+    fn alias_sends_real_title() {
+        let songs = songs();
+        let mut aliases = AliasTable::new();
+        aliases.add("glox", 555);
+
+        let record = record(0, ChartType::Sc).with_title("glox");
+
+        let aliased = aliased_record(&record, Some(&aliases), Some(&songs)).unwrap();
+        assert_eq!(aliased.song_id, 555);
+        let payload = APIPlayRecord::new(&aliased, "V EXTENSION III");
+        assert_eq!(payload.name, "Gloxinia");
+
+        let same = aliased_record(&record, None, Some(&songs)).unwrap();
+        assert_eq!(APIPlayRecord::new(&same, "V EXTENSION III").name, "glox");
+    }
+
+    #[test]
+    fn improvement_check() {
+        let previous = record(0, ChartType::Sc).with_acc(99.2).with_max_combo(true);
+        let mut record = record(0, ChartType::Sc).with_acc(99.5).with_max_combo(true);
+
+        assert!(check_improvement(&record, None).is_ok());
+        assert!(check_improvement(&record, Some(&previous)).is_ok());
+
+        record.is_max_combo = false;
+        assert!(check_improvement(&record, Some(&previous)).is_err());
+
+        record.acc_rate = Some(99.2);
+        record.is_max_combo = true;
+        assert!(check_improvement(&record, Some(&previous)).is_err());
+
+        record.acc_rate = Some(98.0);
+        assert!(check_improvement(&record, Some(&previous)).is_err());
+    }
+}
@@ -0,0 +1,579 @@
+use crate::hangul;
+use crate::AliasTable;
+use crate::{APIError, ButtonMode, OwnedContent, SongCatagory, SongSet};
+
+/// A result of `resolve_song()`
+#[derive(Debug)]
+pub enum SongResolution<'a> {
+    /// Only one song is found
+    Found(&'a SongSet),
+    /// Several songs are found. Pick one with `Song.artist` or `Song.song_cat`.
+    Ambiguous(Vec<&'a SongSet>),
+    NotFound,
+}
+
+impl<'a> SongResolution<'a> {
+    /// Convert to `Result`, with the same errors as V-Archive server
+    /// (`APIError::CannotFindSong` or `APIError::FoundSeveralSongs`)
+    pub fn into_result(self) -> Result<&'a SongSet, APIError> {
+        match self {
+            Self::Found(s) => Ok(s),
+            Self::Ambiguous(_) => Err(APIError::FoundSeveralSongs),
+            Self::NotFound => Err(APIError::CannotFindSong),
+        }
+    }
+
+    /// All found songs
+    pub fn candidates(&self) -> Vec<&'a SongSet> {
+        match self {
+            Self::Found(s) => vec![*s],
+            Self::Ambiguous(list) => list.clone(),
+            Self::NotFound => Vec::new(),
+        }
+    }
+}
+
+/// Make a title easy to compare: lowercase, half-width, and single spaces
+pub(crate) fn normalize_title(title: &str) -> String {
+    let half_width: String = title
+        .chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            c => c,
+        })
+        .collect();
+
+    half_width
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+pub(crate) fn resolution_of(found: Vec<&SongSet>) -> Option<SongResolution<'_>> {
+    match found.len() {
+        0 => None,
+        1 => Some(SongResolution::Found(found[0])),
+        _ => Some(SongResolution::Ambiguous(found)),
+    }
+}
+
+/// Find a song by title on a song list (from `all_songs()`).
+///
+/// It tries exact title first, then case/width-insensitive title, then the start of title.
+/// If several songs are found on a step, all of them are returned as `SongResolution::Ambiguous`.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{all_songs, resolve_song, SongResolution};
+/// #
+/// # fn main() {
+/// let songs = all_songs().unwrap();
+///
+/// match resolve_song(&songs, "gloxinia") {
+///     SongResolution::Found(s) => println!("Song ID: {}", s.content.song_id),
+///     SongResolution::Ambiguous(list) => {
+///         for s in list {
+///             println!("{} - {} ({})", s.content.title, s.content.artist, s.content.song_cat);
+///         }
+///     }
+///     SongResolution::NotFound => println!("No song"),
+/// }
+/// # }
+/// ```
+pub fn resolve_song<'a>(songs: &'a [SongSet], title: &str) -> SongResolution<'a> {
+    resolve_song_inner(songs, title, None)
+}
+
+/// Same as `resolve_song()`, but also with aliases. Aliases are tried after exact title.
+pub fn resolve_song_with_aliases<'a>(
+    songs: &'a [SongSet],
+    title: &str,
+    aliases: &AliasTable,
+) -> SongResolution<'a> {
+    resolve_song_inner(songs, title, Some(aliases))
+}
+
+fn resolve_song_inner<'a>(
+    songs: &'a [SongSet],
+    title: &str,
+    aliases: Option<&AliasTable>,
+) -> SongResolution<'a> {
+    let exact: Vec<&SongSet> = songs.iter().filter(|s| s.content.title == title).collect();
+    if let Some(r) = resolution_of(exact) {
+        return r;
+    }
+
+    if let Some(aliases) = aliases {
+        let song_ids = aliases.song_ids(title);
+        let aliased: Vec<&SongSet> = songs
+            .iter()
+            .filter(|s| song_ids.contains(&s.content.song_id))
+            .collect();
+        if let Some(r) = resolution_of(aliased) {
+            return r;
+        }
+    }
+
+    let query = normalize_title(title);
+    if query.is_empty() {
+        return SongResolution::NotFound;
+    }
+
+    let normalized: Vec<(&SongSet, String)> = songs
+        .iter()
+        .map(|s| (s, normalize_title(&s.content.title)))
+        .collect();
+
+    let same: Vec<&SongSet> = normalized
+        .iter()
+        .filter(|(_, t)| *t == query)
+        .map(|(s, _)| *s)
+        .collect();
+    if let Some(r) = resolution_of(same) {
+        return r;
+    }
+
+    let prefixed: Vec<&SongSet> = normalized
+        .iter()
+        .filter(|(_, t)| t.starts_with(&query))
+        .map(|(s, _)| *s)
+        .collect();
+
+    resolution_of(prefixed).unwrap_or(SongResolution::NotFound)
+}
+
+/// Conditions for `SongIndex::search()`
+#[derive(Debug, Clone)]
+pub struct SearchFilter {
+    /// Song categories to search in (empty for all)
+    pub categories: Vec<SongCatagory>,
+    /// Only songs with a chart for the button mode
+    pub button: Option<ButtonMode>,
+    /// Only songs with a chart in the level range, as `(min, max)`.
+    /// With `button`, the chart should be for the button mode.
+    pub levels: Option<(u8, u8)>,
+    /// The minimum score of results (`0.0` to `1.0`)
+    pub min_score: f64,
+    /// The maximum number of results
+    pub limit: Option<usize>,
+    /// Only songs the player owns
+    pub owned: Option<OwnedContent>,
+}
+
+impl SearchFilter {
+    pub fn new() -> Self {
+        Self {
+            categories: Vec::new(),
+            button: None,
+            levels: None,
+            min_score: 0.5,
+            limit: None,
+            owned: None,
+        }
+    }
+
+    fn matches(&self, song: &SongSet) -> bool {
+        if self
+            .owned
+            .as_ref()
+            .is_some_and(|o| !o.owns_song(&song.content))
+        {
+            return false;
+        }
+        if !self.categories.is_empty() && !self.categories.contains(&song.content.song_cat) {
+            return false;
+        }
+        if self.button.is_none() && self.levels.is_none() {
+            return true;
+        }
+
+        song.charts.iter().any(|c| {
+            self.button.as_ref().is_none_or(|b| &c.button == b)
+                && self
+                    .levels
+                    .is_none_or(|(min, max)| (min..=max).contains(&c.level))
+        })
+    }
+}
+
+impl Default for SearchFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A song found by `SongIndex::search()`
+#[derive(Debug, Clone)]
+pub struct SearchHit<'a> {
+    pub song: &'a SongSet,
+    /// How close to the query (`0.0` to `1.0`, higher is closer)
+    pub score: f64,
+}
+
+/// Normalized text for searching
+#[derive(Debug)]
+struct IndexedText {
+    text: Vec<char>,
+    tokens: Vec<Vec<char>>,
+}
+
+impl IndexedText {
+    fn new(raw: &str) -> Self {
+        let normalized = normalize_title(raw);
+        let tokens = normalized
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.chars().collect())
+            .collect();
+
+        Self {
+            text: normalized.chars().collect(),
+            tokens,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct IndexEntry {
+    title: IndexedText,
+    artist: IndexedText,
+    /// Title without spaces, for chosung queries
+    compact_title: Vec<char>,
+    /// Romanized title, only for a title with Hangul
+    romanized_title: Option<IndexedText>,
+    aliases: Vec<IndexedText>,
+}
+
+impl IndexEntry {
+    fn new(song: &SongSet, aliases: Option<&AliasTable>) -> Self {
+        let title = IndexedText::new(&song.content.title);
+        let compact_title = title
+            .text
+            .iter()
+            .filter(|c| !c.is_whitespace())
+            .copied()
+            .collect();
+        let romanized_title = if title.text.iter().any(|c| hangul::is_syllable(*c)) {
+            Some(IndexedText::new(&hangul::romanize(&song.content.title)))
+        } else {
+            None
+        };
+
+        Self {
+            title,
+            artist: IndexedText::new(&song.content.artist),
+            compact_title,
+            romanized_title,
+            aliases: aliases
+                .map(|a| a.aliases_of(song.content.song_id))
+                .unwrap_or_default()
+                .into_iter()
+                .map(IndexedText::new)
+                .collect(),
+        }
+    }
+}
+
+/// A search index over a song list (from `all_songs()`), for searching with typos.
+///
+/// Build it once, and search many times (e. g. for each key input).
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{all_songs, ButtonMode, SearchFilter, SongIndex};
+/// #
+/// # fn main() {
+/// let songs = all_songs().unwrap();
+/// let index = SongIndex::new(&songs);
+///
+/// let mut filter = SearchFilter::new();
+/// filter.button = Some(ButtonMode::Six);
+/// filter.limit = Some(5);
+///
+/// for hit in index.search("urbn night", &filter) {
+///     println!("[{:.2}] {}", hit.score, hit.song.content.title);
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SongIndex<'a> {
+    songs: &'a [SongSet],
+    entries: Vec<IndexEntry>,
+}
+
+impl<'a> SongIndex<'a> {
+    pub fn new(songs: &'a [SongSet]) -> Self {
+        let entries = songs.iter().map(|s| IndexEntry::new(s, None)).collect();
+
+        Self { songs, entries }
+    }
+
+    /// Build an index with aliases. A song is also found by its aliases.
+    pub fn with_aliases(songs: &'a [SongSet], aliases: &AliasTable) -> Self {
+        let entries = songs
+            .iter()
+            .map(|s| IndexEntry::new(s, Some(aliases)))
+            .collect();
+
+        Self { songs, entries }
+    }
+
+    /// Search songs by title or artist. Results are sorted by score (higher first).
+    ///
+    /// For Korean titles, the query can be initial consonants (e. g. `ㅂㅅ` for `비상`), or
+    /// romanized by Revised Romanization (e. g. `bisang`).
+    pub fn search(&self, query: &str, filter: &SearchFilter) -> Vec<SearchHit<'a>> {
+        let query = IndexedText::new(query);
+        if query.text.is_empty() {
+            return Vec::new();
+        }
+
+        let has_jamo = query.text.iter().any(|c| hangul::is_consonant_jamo(*c));
+        let has_hangul = has_jamo || query.text.iter().any(|c| hangul::is_syllable(*c));
+        let compact_query: Vec<char> = query
+            .text
+            .iter()
+            .filter(|c| !c.is_whitespace())
+            .copied()
+            .collect();
+
+        let mut hits: Vec<SearchHit<'a>> = self
+            .songs
+            .iter()
+            .zip(&self.entries)
+            .filter(|(song, _)| filter.matches(song))
+            .filter_map(|(song, entry)| {
+                let mut score = text_score(&query, &entry.title)
+                    .max(text_score(&query, &entry.artist) * ARTIST_WEIGHT);
+
+                for alias in &entry.aliases {
+                    score = score.max(text_score(&query, alias));
+                }
+                if has_jamo {
+                    score = score.max(jamo_score(&compact_query, &entry.compact_title));
+                }
+                if let (false, Some(romanized)) = (has_hangul, &entry.romanized_title) {
+                    score = score.max(text_score(&query, romanized) * ROMANIZED_WEIGHT);
+                }
+
+                if score >= filter.min_score {
+                    Some(SearchHit { song, score })
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.song.content.title.len().cmp(&b.song.content.title.len()))
+        });
+
+        if let Some(limit) = filter.limit {
+            hits.truncate(limit);
+        }
+
+        hits
+    }
+}
+
+/// Artists are less important than titles on searching
+const ARTIST_WEIGHT: f64 = 0.8;
+
+/// Romanization has several ways to write, so it's a bit less important
+const ROMANIZED_WEIGHT: f64 = 0.95;
+
+/// A score of a query with initial consonants on a title (without spaces)
+fn jamo_score(query: &[char], title: &[char]) -> f64 {
+    if query.is_empty() || query.len() > title.len() {
+        return 0.0;
+    }
+
+    let matches_at = |start: usize| {
+        query
+            .iter()
+            .zip(&title[start..])
+            .all(|(q, t)| hangul::jamo_matches(*q, *t))
+    };
+
+    if matches_at(0) {
+        1.0
+    } else if (1..=title.len() - query.len()).any(matches_at) {
+        0.9
+    } else {
+        0.0
+    }
+}
+
+/// A score of a query on a text (`0.0` to `1.0`)
+fn text_score(query: &IndexedText, target: &IndexedText) -> f64 {
+    if target.text.is_empty() {
+        return 0.0;
+    }
+    if query.text == target.text {
+        return 1.0;
+    }
+    if target.text.starts_with(&query.text) {
+        return 0.95;
+    }
+    if contains_chars(&target.text, &query.text) {
+        return 0.9;
+    }
+
+    let whole = char_similarity(&query.text, &target.text);
+
+    // Every query token should be close to a target token (prefix counts for typing)
+    let tokens = if query.tokens.is_empty() || target.tokens.is_empty() {
+        0.0
+    } else {
+        let sum: f64 = query
+            .tokens
+            .iter()
+            .map(|q| {
+                target
+                    .tokens
+                    .iter()
+                    .map(|t| token_similarity(q, t))
+                    .fold(0.0, f64::max)
+            })
+            .sum();
+        sum / query.tokens.len() as f64 * 0.9
+    };
+
+    whole.max(tokens)
+}
+
+fn contains_chars(text: &[char], part: &[char]) -> bool {
+    part.len() <= text.len() && text.windows(part.len()).any(|w| w == part)
+}
+
+fn token_similarity(query: &[char], token: &[char]) -> f64 {
+    if token.starts_with(query) {
+        return 1.0;
+    }
+    // For a token still being typed, compare with the same length of the start
+    let full = char_similarity(query, token);
+    if query.len() < token.len() {
+        full.max(char_similarity(query, &token[..query.len()]) * 0.9)
+    } else {
+        full
+    }
+}
+
+/// `1.0 - (edit distance / longer length)`
+pub(crate) fn char_similarity(a: &[char], b: &[char]) -> f64 {
+    let longer = a.len().max(b.len());
+    if longer == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longer as f64
+}
+
+/// Levenshtein distance
+pub(crate) fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::songs;
+
+    #[test]
+    fn resolve_song_steps() {
+        let songs = songs();
+
+        assert!(
+            matches!(resolve_song(&songs, "Airwave"), SongResolution::Found(s) if s.content.song_id == 1)
+        );
+        assert!(
+            matches!(resolve_song(&songs, "ＵＲＢＡＮ　night"), SongResolution::Found(s) if s.content.song_id == 3)
+        );
+        assert!(
+            matches!(resolve_song(&songs, "airwave ~ext"), SongResolution::Found(s) if s.content.song_id == 2)
+        );
+        assert_eq!(resolve_song(&songs, "Alone").candidates().len(), 2);
+        assert_eq!(resolve_song(&songs, "ai").candidates().len(), 2);
+        assert!(matches!(
+            resolve_song(&songs, "Oblivion"),
+            SongResolution::NotFound
+        ));
+    }
+
+    #[test]
+    fn fuzzy_search() {
+        let songs = songs();
+        let index = SongIndex::new(&songs);
+        let filter = SearchFilter::new();
+
+        let hits = index.search("urbn night", &filter);
+        assert_eq!(hits[0].song.content.song_id, 3);
+
+        let hits = index.search("marshmelo", &filter);
+        assert_eq!(hits[0].song.content.song_id, 4);
+
+        let mut filter = SearchFilter::new();
+        filter.categories = vec![SongCatagory::from("P3")];
+        let hits = index.search("airwave", &filter);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].song.content.song_id, 2);
+    }
+
+    #[test]
+    fn hangul_search() {
+        let songs = songs();
+        let index = SongIndex::new(&songs);
+        let filter = SearchFilter::new();
+
+        assert_eq!(index.search("ㅂㅅ", &filter)[0].song.content.song_id, 6);
+        assert_eq!(index.search("ㄹㅇ", &filter)[0].song.content.song_id, 7);
+        assert_eq!(index.search("설ㄹ", &filter)[0].song.content.song_id, 7);
+        assert_eq!(index.search("bisang", &filter)[0].song.content.song_id, 6);
+        assert_eq!(index.search("seolleim", &filter)[0].song.content.song_id, 7);
+    }
+
+    #[test]
+    fn alias_lookup() {
+        let songs = songs();
+        let mut aliases = AliasTable::new();
+        aliases.add("UN", 3);
+        aliases.add("alone", 4);
+
+        assert!(matches!(
+            resolve_song_with_aliases(&songs, "un", &aliases),
+            SongResolution::Found(s) if s.content.song_id == 3
+        ));
+        // A real title goes first
+        assert_eq!(
+            resolve_song_with_aliases(&songs, "Alone", &aliases)
+                .candidates()
+                .len(),
+            2
+        );
+
+        let index = SongIndex::with_aliases(&songs, &aliases);
+        assert_eq!(
+            index.search("un", &SearchFilter::new())[0]
+                .song
+                .content
+                .song_id,
+            3
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{ButtonMode, ChartRef, UserChartRecord};
+use std::collections::HashSet;
+
+/// A consistency criterion for a floor (e. g. 80% of charts at 98% or more).
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{load_user_floor_board, SkillCriterion};
+/// #
+/// # fn main() {
+/// let board = load_user_floor_board("내꺼", 6, "SC").unwrap();
+/// let records = board.floors.iter().flat_map(|f| f.records.iter());
+///
+/// for skill in SkillCriterion::default().estimate(records) {
+///     println!("{}B: {:?}", skill.button, skill.floor);
+///     for e in &skill.evidence {
+///         println!("  {}: {}/{} ({})", e.floor, e.passed, e.counted, e.meets);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkillCriterion {
+    /// Accuracy for a chart to pass
+    pub min_acc: f64,
+    /// Rate of passed charts on a floor (`0.0` to `1.0`)
+    pub min_rate: f64,
+    /// Minimum played charts on a floor; floors with fewer ones can't meet the criterion
+    pub min_played: usize,
+    /// Count unplayed charts as not passed (otherwise, only played charts are counted)
+    pub count_unplayed: bool,
+}
+
+impl SkillCriterion {
+    pub fn new(min_acc: f64, min_rate: f64) -> Self {
+        Self {
+            min_acc,
+            min_rate,
+            min_played: 3,
+            count_unplayed: false,
+        }
+    }
+
+    /// Skill floors for each button mode, in order of button mode.
+    /// Records without `floor_level` are skipped, and records for a same chart are counted once.
+    pub fn estimate<'a, I>(&self, records: I) -> Vec<SkillFloor>
+    where
+        I: IntoIterator<Item = &'a UserChartRecord>,
+    {
+        let mut seen: HashSet<ChartRef> = HashSet::new();
+        let mut skills: Vec<SkillFloor> = Vec::new();
+
+        for r in records {
+            let floor = match r.floor_level {
+                Some(f) => f,
+                None => continue,
+            };
+            if !seen.insert(r.chart_ref()) {
+                continue;
+            }
+
+            let skill = match skills.iter().position(|s| s.button == r.button) {
+                Some(i) => &mut skills[i],
+                None => {
+                    skills.push(SkillFloor {
+                        button: r.button.clone(),
+                        floor: None,
+                        evidence: Vec::new(),
+                    });
+                    skills.last_mut().unwrap()
+                }
+            };
+            let evidence = match skill.evidence.iter().position(|e| e.floor == floor) {
+                Some(i) => &mut skill.evidence[i],
+                None => {
+                    skill.evidence.push(FloorEvidence::new(floor));
+                    skill.evidence.last_mut().unwrap()
+                }
+            };
+
+            evidence.total += 1;
+            if let Some(acc) = r.acc_rate {
+                evidence.played += 1;
+                if acc >= self.min_acc {
+                    evidence.passed += 1;
+                }
+            }
+        }
+
+        for skill in &mut skills {
+            skill.evidence.sort_by(|a, b| a.floor.total_cmp(&b.floor));
+            for e in &mut skill.evidence {
+                e.counted = if self.count_unplayed {
+                    e.total
+                } else {
+                    e.played
+                };
+                e.rate = match e.counted {
+                    0 => 0.0,
+                    n => e.passed as f64 / n as f64,
+                };
+                e.meets = e.played >= self.min_played && e.rate >= self.min_rate;
+            }
+            skill.floor = skill
+                .evidence
+                .iter()
+                .rev()
+                .find(|e| e.meets)
+                .map(|e| e.floor);
+        }
+        skills.sort_by(|a, b| a.button.cmp(&b.button));
+        skills
+    }
+}
+
+impl Default for SkillCriterion {
+    /// 80% of played charts at 98% or more, with 3 played charts at least
+    fn default() -> Self {
+        Self::new(98.0, 0.8)
+    }
+}
+
+/// Counts on a floor for `SkillCriterion`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloorEvidence {
+    pub floor: f64,
+    /// All charts on the floor, with unplayed ones
+    pub total: usize,
+    pub played: usize,
+    /// Charts with `min_acc` or more
+    pub passed: usize,
+    /// Charts counted for the rate (`total` or `played`, by `count_unplayed`)
+    pub counted: usize,
+    /// Rate of passed charts
+    pub rate: f64,
+    /// The floor meets the criterion
+    pub meets: bool,
+}
+
+impl FloorEvidence {
+    fn new(floor: f64) -> Self {
+        Self {
+            floor,
+            total: 0,
+            played: 0,
+            passed: 0,
+            counted: 0,
+            rate: 0.0,
+            meets: false,
+        }
+    }
+}
+
+/// An estimated skill floor of a button mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkillFloor {
+    pub button: ButtonMode,
+    /// The highest floor meeting the criterion (`None` if there's no such floor)
+    pub floor: Option<f64>,
+    /// Counts for each floor, in ascending order
+    pub evidence: Vec<FloorEvidence>,
+}
+
+impl SkillFloor {
+    /// Floors below the skill floor not meeting the criterion
+    pub fn gaps(&self) -> Vec<&FloorEvidence> {
+        match self.floor {
+            Some(floor) => self
+                .evidence
+                .iter()
+                .filter(|e| e.floor < floor && !e.meets)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use crate::ChartType;
+
+    fn record(song_id: usize, button: ButtonMode, acc: Option<f64>, floor: f64) -> UserChartRecord {
+        test_util::record(song_id, ChartType::Sc)
+            .with_button(button)
+            .with_acc(acc)
+            .with_floor(floor)
+    }
+
+    #[test]
+    fn skill_floor() {
+        let six = |id, acc, floor| record(id, ButtonMode::Six, acc, floor);
+        let records = vec![
+            six(1, Some(99.0), 13.0),
+            six(2, Some(98.5), 13.0),
+            six(3, Some(97.0), 13.0),
+            six(4, Some(98.0), 13.5),
+            six(5, Some(99.0), 13.5),
+            six(6, Some(99.2), 13.5),
+            six(7, None, 13.5),
+            six(8, Some(99.0), 14.0),
+            six(9, Some(99.5), 14.0),
+            six(9, Some(99.5), 14.0),
+            record(1, ButtonMode::Four, Some(90.0), 10.0),
+        ];
+
+        let skills = SkillCriterion::default().estimate(&records);
+        assert_eq!(skills.len(), 2);
+        assert_eq!(skills[0].button, ButtonMode::Four);
+        assert_eq!(skills[0].floor, None);
+
+        let six = &skills[1];
+        assert_eq!(six.floor, Some(13.5));
+        assert_eq!(six.evidence.len(), 3);
+        // 2 of 3 on 13.0, and only 2 played (not enough) on 14.0
+        assert!(!six.evidence[0].meets);
+        assert_eq!(six.evidence[2].total, 2);
+        assert!(!six.evidence[2].meets);
+        assert_eq!(six.gaps().len(), 1);
+
+        let strict = SkillCriterion {
+            count_unplayed: true,
+            ..SkillCriterion::default()
+        };
+        let skills = strict.estimate(&records);
+        assert_eq!(skills[1].evidence[1].counted, 4);
+        assert_eq!(skills[1].evidence[1].rate, 0.75);
+        assert_eq!(skills[1].floor, None);
+    }
+}
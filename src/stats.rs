@@ -0,0 +1,203 @@
+use crate::{OwnedContent, UserChartRecord, UserFloorRecordBoard, UserFloorRecordSet};
+use std::fmt;
+
+/// Statistics on records (of a floor, or of a board)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RecordStats {
+    /// Numbers of records
+    pub total: usize,
+    /// Records with accuracy
+    pub played: usize,
+    pub unplayed: usize,
+    /// Records with MAX COMBO, including perfect ones (unlike `LampCounts.max_combo`)
+    pub max_combo_or_better: usize,
+    /// Records with 100% accuracy
+    pub perfect: usize,
+    /// Accuracy of played records (`None` if nothing is played)
+    pub mean_acc: Option<f64>,
+    pub median_acc: Option<f64>,
+    pub min_acc: Option<f64>,
+    pub rating_sum: f64,
+}
+
+impl RecordStats {
+    pub fn of<'a, I: IntoIterator<Item = &'a UserChartRecord>>(records: I) -> Self {
+        let mut stats = Self::default();
+        let mut accs = Vec::new();
+
+        for r in records {
+            stats.total += 1;
+            if let Some(acc) = r.acc_rate {
+                accs.push(acc);
+            }
+            if r.is_max_combo {
+                stats.max_combo_or_better += 1;
+            }
+            stats.rating_sum += r.user_rating.unwrap_or(0.0);
+        }
+
+        accs.sort_by(|a, b| a.total_cmp(b));
+        stats.played = accs.len();
+        stats.unplayed = stats.total - stats.played;
+        stats.perfect = accs.iter().filter(|a| **a >= 100.0).count();
+        stats.min_acc = accs.first().copied();
+        if !accs.is_empty() {
+            stats.mean_acc = Some(accs.iter().sum::<f64>() / accs.len() as f64);
+            let middle = accs.len() / 2;
+            stats.median_acc = Some(if accs.len() % 2 == 0 {
+                (accs[middle - 1] + accs[middle]) / 2.0
+            } else {
+                accs[middle]
+            });
+        }
+
+        stats
+    }
+
+    /// Rate of played records (`0.0` to `1.0`)
+    pub fn played_rate(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.played as f64 / self.total as f64
+        }
+    }
+}
+
+/// Statistics on a board: totals and each floor
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardStats {
+    pub total: RecordStats,
+    /// `(floor number, statistics)`, in order of the board
+    pub floors: Vec<(f64, RecordStats)>,
+}
+
+/// The number of parsed records is not same as `total_count` of the board
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountMismatch {
+    pub total_count: usize,
+    pub parsed_count: usize,
+}
+
+impl fmt::Display for CountMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Board has {} records, but total count is {}",
+            self.parsed_count, self.total_count
+        )
+    }
+}
+
+impl UserFloorRecordSet {
+    pub fn stats(&self) -> RecordStats {
+        RecordStats::of(&self.records)
+    }
+}
+
+impl UserFloorRecordBoard {
+    /// Statistics on the board and each floor
+    /// ## Example
+    /// ```no_run
+    /// # use v_archive_rs::load_user_floor_board;
+    /// #
+    /// # fn main() {
+    /// let board = load_user_floor_board("내꺼", 6, "SC").unwrap();
+    /// let stats = board.stats();
+    ///
+    /// println!("Played: {}/{}", stats.total.played, stats.total.total);
+    /// for (floor, s) in &stats.floors {
+    ///     println!("{}: {:?} (median)", floor, s.median_acc);
+    /// }
+    /// # }
+    /// ```
+    pub fn stats(&self) -> BoardStats {
+        self.stats_for(None)
+    }
+
+    /// Same as `stats()`, but only with records of songs the player owns (if given)
+    pub fn stats_for(&self, owned: Option<&OwnedContent>) -> BoardStats {
+        let is_owned = |r: &&UserChartRecord| owned.is_none_or(|o| o.owns_record(r));
+
+        BoardStats {
+            total: RecordStats::of(
+                self.floors
+                    .iter()
+                    .flat_map(|f| f.records.iter())
+                    .filter(is_owned),
+            ),
+            floors: self
+                .floors
+                .iter()
+                .map(|f| {
+                    (
+                        f.floor_number,
+                        RecordStats::of(f.records.iter().filter(is_owned)),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Check the number of records on floors is same as `total_count`
+    pub fn check_count(&self) -> Result<(), CountMismatch> {
+        let parsed_count = self.floors.iter().map(|f| f.records.len()).sum();
+
+        if parsed_count == self.total_count {
+            Ok(())
+        } else {
+            Err(CountMismatch {
+                total_count: self.total_count,
+                parsed_count,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_floor_board_parse;
+
+    const BOARD_JSON: &str = r#"{
+        "success": true, "board": "SC", "button": "6", "totalCount": 5,
+        "floors": [
+            {"floorNumber": 14.2, "patterns": [
+                {"title": 1, "name": "A", "composer": "a", "pattern": "SC", "score": "100.00", "maxCombo": 1, "djpower": 10.0, "rating": 200.0, "dlc": "R", "dlcCode": "R", "updatedAt": null},
+                {"title": 2, "name": "B", "composer": "b", "pattern": "SC", "score": "98.50", "maxCombo": 0, "djpower": 9.0, "rating": 180.0, "dlc": "R", "dlcCode": "R", "updatedAt": null},
+                {"title": 3, "name": "C", "composer": "c", "pattern": "SC", "score": null, "maxCombo": 0, "djpower": 0.0, "rating": 0.0, "dlc": "R", "dlcCode": "R", "updatedAt": null}
+            ]},
+            {"floorNumber": 14.0, "patterns": [
+                {"title": 4, "name": "D", "composer": "d", "pattern": "SC", "score": "99.00", "maxCombo": 1, "djpower": 9.5, "rating": 190.0, "dlc": "R", "dlcCode": "R", "updatedAt": null}
+            ]}
+        ]
+    }"#;
+
+    #[test]
+    fn board_stats() {
+        let board = user_floor_board_parse(BOARD_JSON.to_string());
+        let stats = board.stats();
+
+        let top = &stats.floors[0].1;
+        assert_eq!(stats.floors[0].0, 14.2);
+        assert_eq!((top.total, top.played, top.unplayed), (3, 2, 1));
+        // The perfect record is also counted for MAX COMBO
+        assert_eq!((top.max_combo_or_better, top.perfect), (1, 1));
+        assert_eq!(top.mean_acc, Some(99.25));
+        assert_eq!(top.min_acc, Some(98.5));
+        assert_eq!(top.rating_sum, 380.0);
+
+        assert_eq!(stats.total.total, 4);
+        assert_eq!(stats.total.median_acc, Some(99.0));
+        assert_eq!(stats.total.max_combo_or_better, 2);
+        assert_eq!(stats.total.played_rate(), 0.75);
+
+        assert_eq!(
+            board.check_count(),
+            Err(CountMismatch {
+                total_count: 5,
+                parsed_count: 4,
+            })
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Fixtures shared by unit tests
+use crate::{all_songs_parse, ButtonMode, ChartType, SongCatagory, SongSet, UserChartRecord};
+
+/// A song list for tests, like a response of `all_songs()`.
+///
+/// * 1 ~ 8: real titles (with two `Alone`s), for searching and resolving
+/// * 11 ~ 16: `A` ~ `F`, charts around floor 13.0 (and `F` with ratings)
+/// * 555: `Gloxinia`, with every field on 6B SC
+pub(crate) const SONGS_JSON: &str = r#"[
+    {"title": 1, "name": "Airwave", "composer": "Forte Escape", "dlcCode": "R", "dlc": "RESPECT", "patterns": {
+        "4B": {"NM": {"level": 4}, "SC": {"level": 12, "floor": 12.3}}, "5B": {}, "6B": {"SC": {"level": 13, "floor": 13.5}}, "8B": {}}},
+    {"title": 2, "name": "Airwave ~Extended Mix~", "composer": "Forte Escape", "dlcCode": "P3", "dlc": "PORTABLE 3", "patterns": {"4B": {}, "5B": {}, "6B": {}, "8B": {}}},
+    {"title": 3, "name": "Urban Night", "composer": "Electronic Boutique", "dlcCode": "ES", "dlc": "EMOTIONAL S.", "patterns": {
+        "4B": {}, "5B": {}, "6B": {"MX": {"level": 14}, "SC": {"level": 14, "floor": 14.2}}, "8B": {}}},
+    {"title": 4, "name": "Alone", "composer": "Marshmello", "dlcCode": "CY", "dlc": "CYTUS", "patterns": {
+        "4B": {}, "5B": {}, "6B": {"SC": {"level": 15, "floor": 14.8}}, "8B": {"SC": {"level": 15}}}},
+    {"title": 5, "name": "Alone", "composer": "Nauts", "dlcCode": "GG", "dlc": "GUILTY GEAR", "patterns": {"4B": {}, "5B": {}, "6B": {}, "8B": {}}},
+    {"title": 6, "name": "비상 ~Stay With Me~", "composer": "Mycin.T", "dlcCode": "R", "dlc": "RESPECT", "patterns": {"4B": {}, "5B": {}, "6B": {}, "8B": {}}},
+    {"title": 7, "name": "설레임", "composer": "Forte Escape", "dlcCode": "P1", "dlc": "PORTABLE 1", "patterns": {"4B": {}, "5B": {}, "6B": {}, "8B": {}}},
+    {"title": 8, "name": "Night/Day", "composer": "h", "dlcCode": "R", "dlc": "RESPECT", "patterns": {
+        "4B": {"NM": {"level": 4}}, "5B": {}, "6B": {}, "8B": {}}},
+    {"title": 11, "name": "A", "composer": "a", "dlcCode": "R", "dlc": "RESPECT", "patterns": {
+        "4B": {}, "5B": {}, "6B": {"NM": {"level": 7}, "SC": {"level": 13, "floor": 13.0}}, "8B": {}}},
+    {"title": 12, "name": "B", "composer": "b", "dlcCode": "R", "dlc": "RESPECT", "patterns": {
+        "4B": {}, "5B": {}, "6B": {"SC": {"level": 13, "floor": 13.0}}, "8B": {}}},
+    {"title": 13, "name": "C", "composer": "c", "dlcCode": "R", "dlc": "RESPECT", "patterns": {
+        "4B": {}, "5B": {}, "6B": {"SC": {"level": 13, "floor": 13.0}}, "8B": {}}},
+    {"title": 14, "name": "D", "composer": "d", "dlcCode": "R", "dlc": "RESPECT", "patterns": {
+        "4B": {}, "5B": {}, "6B": {"SC": {"level": 13, "floor": 13.2}}, "8B": {}}},
+    {"title": 15, "name": "E", "composer": "e", "dlcCode": "VE", "dlc": "V EXTENSION", "patterns": {
+        "4B": {}, "5B": {}, "6B": {"SC": {"level": 13, "floor": 12.9}}, "8B": {}}},
+    {"title": 16, "name": "F", "composer": "f", "dlcCode": "R", "dlc": "RESPECT", "patterns": {
+        "4B": {}, "5B": {}, "6B": {"NM": {"level": 8, "rating": 90.0}, "HD": {"level": 11, "rating": 150.0}, "SC": {"level": 15, "floor": 15.0, "rating": 250.0}},
+        "8B": {"SC": {"level": 15, "rating": 300.0}}}},
+    {"title": 555, "name": "Gloxinia", "composer": "ESTi", "dlcCode": "VE3", "dlc": "V EXTENSION III", "patterns": {
+        "4B": {"NM": {"level": 5}}, "5B": {}, "6B": {"NM": {"level": 6}, "MX": {"level": 14}, "SC": {"level": 14, "floor": 14.2, "rating": 210.5}}, "8B": {}}}
+]"#;
+
+pub(crate) fn songs() -> Vec<SongSet> {
+    all_songs_parse(SONGS_JSON.to_string())
+}
+
+/// A 6B record for a chart, not played. Fill other fields with `with_*()`.
+pub(crate) fn record(song_id: usize, chart_type: ChartType) -> UserChartRecord {
+    UserChartRecord {
+        song_id,
+        button: ButtonMode::Six,
+        chart_type,
+        ..UserChartRecord::new()
+    }
+}
+
+impl UserChartRecord {
+    pub(crate) fn with_title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub(crate) fn with_button(mut self, button: ButtonMode) -> Self {
+        self.button = button;
+        self
+    }
+
+    /// `with_acc(99.5)`, or `with_acc(None)` for an unplayed one
+    pub(crate) fn with_acc(mut self, acc: impl Into<Option<f64>>) -> Self {
+        self.acc_rate = acc.into();
+        self
+    }
+
+    pub(crate) fn with_max_combo(mut self, is_max_combo: bool) -> Self {
+        self.is_max_combo = is_max_combo;
+        self
+    }
+
+    pub(crate) fn with_level(mut self, level: u8) -> Self {
+        self.chart_level = Some(level);
+        self
+    }
+
+    pub(crate) fn with_floor(mut self, floor: f64) -> Self {
+        self.floor_level = Some(floor);
+        self
+    }
+
+    pub(crate) fn with_rating(mut self, rating: f64) -> Self {
+        self.user_rating = Some(rating);
+        self
+    }
+
+    pub(crate) fn with_category(mut self, category: &str) -> Self {
+        self.song_cat = Some(SongCatagory::from(category));
+        self
+    }
+}
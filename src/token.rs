@@ -0,0 +1,247 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use zeroize::Zeroize;
+
+/// An environment variable for a user number
+pub const USER_NUM_ENV: &str = "V_ARCHIVE_USER_NUM";
+/// An environment variable for a user token
+pub const USER_TOKEN_ENV: &str = "V_ARCHIVE_USER_TOKEN";
+
+/// Errors on loading `UserToken`
+#[derive(Debug)]
+pub enum TokenError {
+    /// The token is not a UUID (e. g.: `95d6c422-52b4-4016-8587-38c46a2e7917`)
+    InvalidToken,
+    InvalidUserNum(String),
+    MissingEnv(String),
+    NoProfile(String),
+    ConfigError(String),
+    IOError(io::Error),
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidToken => write!(f, "Token is not a valid UUID"),
+            Self::InvalidUserNum(n) => write!(f, "Invalid user number: {}", n),
+            Self::MissingEnv(v) => write!(f, "Environment variable is not set: {}", v),
+            Self::NoProfile(p) => write!(f, "No profile: {}", p),
+            Self::ConfigError(m) => write!(f, "Config file error: {}", m),
+            Self::IOError(e) => write!(f, "IO error: {}", e),
+        }
+    }
+}
+
+/// A user's token for registering records.
+///
+/// The token is hidden on `Debug` and `Display`, and wiped from memory on drop.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::UserToken;
+/// #
+/// # fn main() {
+/// // From `V_ARCHIVE_USER_NUM` and `V_ARCHIVE_USER_TOKEN`
+/// let token = UserToken::from_env().unwrap();
+/// println!("{}", token); // User #1 (token: ********)
+/// # }
+/// ```
+pub struct UserToken {
+    pub user_num: usize,
+    user_token: String,
+}
+
+impl UserToken {
+    /// Make a token. `user_token` should be a UUID.
+    pub fn new(user_num: usize, mut user_token: String) -> Result<Self, TokenError> {
+        if is_uuid(&user_token) {
+            Ok(Self {
+                user_num,
+                user_token,
+            })
+        } else {
+            user_token.zeroize();
+            Err(TokenError::InvalidToken)
+        }
+    }
+
+    /// Load a token from `V_ARCHIVE_USER_NUM` and `V_ARCHIVE_USER_TOKEN`
+    pub fn from_env() -> Result<Self, TokenError> {
+        let user_num =
+            env::var(USER_NUM_ENV).map_err(|_| TokenError::MissingEnv(USER_NUM_ENV.to_owned()))?;
+        let user_num = parse_user_num(&user_num)?;
+        let user_token = env::var(USER_TOKEN_ENV)
+            .map_err(|_| TokenError::MissingEnv(USER_TOKEN_ENV.to_owned()))?;
+
+        Self::new(user_num, user_token)
+    }
+
+    /// Load a token of a profile from a config file (see `TokenProfiles`)
+    pub fn from_profile<P: AsRef<Path>>(path: P, profile: &str) -> Result<Self, TokenError> {
+        TokenProfiles::load(path)?.take(profile)
+    }
+
+    /// The token itself, only for sending requests
+    #[cfg(feature = "write")]
+    pub(crate) fn secret(&self) -> &str {
+        &self.user_token
+    }
+}
+
+impl fmt::Debug for UserToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserToken")
+            .field("user_num", &self.user_num)
+            .field("user_token", &"********")
+            .finish()
+    }
+}
+
+impl fmt::Display for UserToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "User #{} (token: ********)", self.user_num)
+    }
+}
+
+impl Drop for UserToken {
+    fn drop(&mut self) {
+        self.user_token.zeroize();
+    }
+}
+
+/// Named tokens from a config file, for several users on one machine.
+///
+/// A config file is TOML like:
+/// ```toml
+/// [profiles.alice]
+/// user_num = 1
+/// user_token = "95d6c422-52b4-4016-8587-38c46a2e7917"
+///
+/// [profiles.bob]
+/// user_num = 2
+/// user_token = "0d3b8f0e-6f0a-4a43-9d57-2f7d3c1e8a51"
+/// ```
+#[derive(Debug)]
+pub struct TokenProfiles {
+    profiles: BTreeMap<String, UserToken>,
+}
+
+impl TokenProfiles {
+    /// Load profiles from a config file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TokenError> {
+        let mut text = fs::read_to_string(path).map_err(TokenError::IOError)?;
+        let profiles = Self::parse(&text);
+        text.zeroize();
+        profiles
+    }
+
+    fn parse(text: &str) -> Result<Self, TokenError> {
+        #[derive(Deserialize)]
+        struct ConfigProfile {
+            user_num: usize,
+            user_token: String,
+        }
+
+        #[derive(Deserialize)]
+        struct ConfigFile {
+            #[serde(default)]
+            profiles: BTreeMap<String, ConfigProfile>,
+        }
+
+        let config: ConfigFile =
+            toml::from_str(text).map_err(|e| TokenError::ConfigError(e.message().to_owned()))?;
+
+        let mut profiles = BTreeMap::new();
+        let mut error = None;
+        for (name, p) in config.profiles {
+            match UserToken::new(p.user_num, p.user_token) {
+                Ok(t) => {
+                    profiles.insert(name, t);
+                }
+                Err(_) => {
+                    error = Some(TokenError::ConfigError(format!(
+                        "Token is not a valid UUID on profile \"{}\"",
+                        name
+                    )));
+                }
+            }
+        }
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(Self { profiles }),
+        }
+    }
+
+    /// Names of profiles, in order
+    pub fn names(&self) -> Vec<&str> {
+        self.profiles.keys().map(|n| n.as_str()).collect()
+    }
+
+    pub fn get(&self, profile: &str) -> Option<&UserToken> {
+        self.profiles.get(profile)
+    }
+
+    /// Take a token of a profile out
+    pub fn take(mut self, profile: &str) -> Result<UserToken, TokenError> {
+        self.profiles
+            .remove(profile)
+            .ok_or(TokenError::NoProfile(profile.to_owned()))
+    }
+}
+
+fn parse_user_num(text: &str) -> Result<usize, TokenError> {
+    text.trim()
+        .parse()
+        .map_err(|_| TokenError::InvalidUserNum(text.to_owned()))
+}
+
+/// Whether the text is a UUID like `95d6c422-52b4-4016-8587-38c46a2e7917`
+fn is_uuid(text: &str) -> bool {
+    let groups: Vec<&str> = text.split('-').collect();
+    let lengths = [8, 4, 4, 4, 12];
+
+    groups.len() == lengths.len()
+        && groups
+            .iter()
+            .zip(lengths)
+            .all(|(g, l)| g.len() == l && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_check_and_redact() {
+        let token = UserToken::new(1, "95d6c422-52b4-4016-8587-38c46a2e7917".to_string()).unwrap();
+        assert!(!format!("{:?}", token).contains("95d6c422"));
+        assert!(!token.to_string().contains("95d6c422"));
+
+        assert!(matches!(
+            UserToken::new(1, "95d6c422-52b4-4016-8587".to_string()),
+            Err(TokenError::InvalidToken)
+        ));
+    }
+
+    #[test]
+    fn token_profiles() {
+        let config = r#"
+            [profiles.alice]
+            user_num = 1
+            user_token = "95d6c422-52b4-4016-8587-38c46a2e7917"
+
+            [profiles.bob]
+            user_num = 2
+            user_token = "0d3b8f0e-6f0a-4a43-9d57-2f7d3c1e8a51"
+        "#;
+
+        let profiles = TokenProfiles::parse(config).unwrap();
+        assert_eq!(profiles.names(), vec!["alice", "bob"]);
+        assert_eq!(profiles.take("bob").unwrap().user_num, 2);
+    }
+}
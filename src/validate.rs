@@ -0,0 +1,260 @@
+use crate::alias::apply_alias;
+use crate::{AliasTable, ButtonMode, ChartType, SongCatagory, SongSet, UserChartRecord};
+use std::fmt;
+
+/// A problem on a record, found before or while registering it
+#[derive(Debug)]
+pub enum RecordProblem {
+    EmptyTitle,
+    MissingCategory,
+    UnknownDlc(String),
+    MissingAccuracy,
+    AccuracyOutOfRange(f64),
+    /// 100% accuracy needs MAX COMBO
+    PerfectWithoutMaxCombo,
+    UnknownSongId(usize),
+    UnknownTitle(String),
+    /// Several songs have the title, and `song_cat` cannot pick one
+    AmbiguousTitle(String),
+    TitleMismatch {
+        expected: String,
+        found: String,
+    },
+    ChartNotFound(ButtonMode, ChartType),
+    CategoryMismatch {
+        expected: SongCatagory,
+        found: SongCatagory,
+    },
+    LevelMismatch {
+        expected: u8,
+        found: u8,
+    },
+    NotAccepted,
+}
+
+impl fmt::Display for RecordProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyTitle => write!(f, "Title is empty"),
+            Self::MissingCategory => write!(f, "Song category is missing"),
+            Self::UnknownDlc(c) => write!(f, "Unknown DLC: {}", c),
+            Self::MissingAccuracy => write!(f, "Accuracy rate is missing"),
+            Self::AccuracyOutOfRange(a) => write!(f, "Accuracy rate is out of range: {}", a),
+            Self::PerfectWithoutMaxCombo => write!(f, "100% accuracy without MAX COMBO"),
+            Self::UnknownSongId(id) => write!(f, "Unknown song ID: {}", id),
+            Self::UnknownTitle(t) => write!(f, "Unknown title: {}", t),
+            Self::AmbiguousTitle(t) => write!(f, "Several songs for the title: {}", t),
+            Self::TitleMismatch { expected, found } => {
+                write!(f, "Title should be \"{}\", not \"{}\"", expected, found)
+            }
+            Self::ChartNotFound(b, t) => write!(f, "No chart for {}B {}", b, t),
+            Self::CategoryMismatch { expected, found } => {
+                write!(f, "Category should be {}, not {}", expected, found)
+            }
+            Self::LevelMismatch { expected, found } => {
+                write!(f, "Level should be {}, not {}", expected, found)
+            }
+            Self::NotAccepted => write!(f, "Server did not accept the record"),
+        }
+    }
+}
+
+/// Problems found on a record itself, without any song data
+pub(crate) fn record_problems(record: &UserChartRecord) -> Vec<RecordProblem> {
+    let mut problems = Vec::new();
+
+    if record.title.trim().is_empty() {
+        problems.push(RecordProblem::EmptyTitle);
+    }
+
+    match record.acc_rate {
+        None => problems.push(RecordProblem::MissingAccuracy),
+        Some(a) if !(0.0..=100.0).contains(&a) => {
+            problems.push(RecordProblem::AccuracyOutOfRange(a))
+        }
+        Some(a) if a == 100.0 && !record.is_max_combo => {
+            problems.push(RecordProblem::PerfectWithoutMaxCombo)
+        }
+        Some(_) => {}
+    }
+
+    problems
+}
+
+/// Problems found on a record by checking with song data
+pub(crate) fn song_problems(record: &UserChartRecord, songs: &[SongSet]) -> Vec<RecordProblem> {
+    let mut problems = Vec::new();
+
+    let song = if record.song_id != 0 {
+        match songs.iter().find(|s| s.content.song_id == record.song_id) {
+            Some(s) => {
+                if s.content.title != record.title {
+                    problems.push(RecordProblem::TitleMismatch {
+                        expected: s.content.title.clone(),
+                        found: record.title.clone(),
+                    });
+                }
+                s
+            }
+            None => {
+                problems.push(RecordProblem::UnknownSongId(record.song_id));
+                return problems;
+            }
+        }
+    } else {
+        let title = record.title.trim();
+        let mut found: Vec<&SongSet> = songs.iter().filter(|s| s.content.title == title).collect();
+
+        if found.len() > 1 {
+            if let Some(c) = &record.song_cat {
+                found.retain(|s| &s.content.song_cat == c);
+            }
+        }
+
+        match found.len() {
+            0 => {
+                if !title.is_empty() {
+                    problems.push(RecordProblem::UnknownTitle(title.to_owned()));
+                }
+                return problems;
+            }
+            1 => found[0],
+            _ => {
+                problems.push(RecordProblem::AmbiguousTitle(title.to_owned()));
+                return problems;
+            }
+        }
+    };
+
+    if let Some(c) = &record.song_cat {
+        if c != &song.content.song_cat {
+            problems.push(RecordProblem::CategoryMismatch {
+                expected: song.content.song_cat.clone(),
+                found: c.clone(),
+            });
+        }
+    }
+
+    let chart = song
+        .charts
+        .iter()
+        .find(|c| c.button == record.button && c.chart_type == record.chart_type);
+
+    match chart {
+        None => problems.push(RecordProblem::ChartNotFound(
+            record.button.clone(),
+            record.chart_type.clone(),
+        )),
+        Some(c) => {
+            if let Some(l) = record.chart_level {
+                if l != c.level {
+                    problems.push(RecordProblem::LevelMismatch {
+                        expected: c.level,
+                        found: l,
+                    });
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Check a record with a song list (from `all_songs()`), before registering it.
+///
+/// It returns every problem found on the record, not only the first one.
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{all_songs, validate_record, UserChartRecord};
+/// #
+/// # fn example(record: UserChartRecord) {
+/// let songs = all_songs().unwrap();
+///
+/// if let Err(problems) = validate_record(&record, &songs) {
+///     for p in problems {
+///         println!("{}", p);
+///     }
+/// }
+/// # }
+/// ```
+pub fn validate_record(
+    record: &UserChartRecord,
+    songs: &[SongSet],
+) -> Result<(), Vec<RecordProblem>> {
+    let mut problems = record_problems(record);
+    problems.append(&mut song_problems(record, songs));
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+/// Same as `validate_record()`, but also with aliases.
+/// If the title is an alias for only one song, the record is checked with the real title.
+pub fn validate_record_with_aliases(
+    record: &UserChartRecord,
+    songs: &[SongSet],
+    aliases: &AliasTable,
+) -> Result<(), Vec<RecordProblem>> {
+    let mut record = record.clone();
+    apply_alias(&mut record, songs, aliases);
+    validate_record(&record, songs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{record, songs};
+
+    #[test]
+    fn validate_all_problems() {
+        let songs = songs();
+        let mut record = record(0, ChartType::Sc)
+            .with_title("Gloxinia")
+            .with_acc(99.5)
+            .with_category("VE3");
+
+        assert!(validate_record(&record, &songs).is_ok());
+
+        record.button = ButtonMode::Eight;
+        record.acc_rate = Some(100.0);
+        record.song_cat = Some(SongCatagory::from("VE4"));
+
+        match validate_record(&record, &songs) {
+            Ok(_) => panic!("this should be become to error."),
+            Err(p) => {
+                assert_eq!(p.len(), 3);
+                assert!(matches!(p[0], RecordProblem::PerfectWithoutMaxCombo));
+                assert!(matches!(p[1], RecordProblem::CategoryMismatch { .. }));
+                assert!(matches!(
+                    p[2],
+                    RecordProblem::ChartNotFound(ButtonMode::Eight, _)
+                ));
+            }
+        };
+
+        record.title = "Gloxina".to_string();
+
+        match validate_record(&record, &songs) {
+            Ok(_) => panic!("this should be become to error."),
+            Err(p) => assert!(matches!(p[1], RecordProblem::UnknownTitle(_))),
+        };
+    }
+
+    #[test]
+    fn validate_with_aliases() {
+        let songs = songs();
+        let mut aliases = AliasTable::new();
+        aliases.add("glox", 555);
+
+        let record = record(0, ChartType::Sc)
+            .with_title("GLOX")
+            .with_acc(99.5)
+            .with_category("VE3");
+
+        assert!(validate_record(&record, &songs).is_err());
+        assert!(validate_record_with_aliases(&record, &songs, &aliases).is_ok());
+    }
+}
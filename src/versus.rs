@@ -0,0 +1,371 @@
+use crate::{
+    APIError, ButtonMode, Catalog, ChartRef, Client, FloorBoardType, SongCatagory, UserChartRecord,
+    UserFloorRecordBoard, UserTierRecordTable,
+};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// A user's records for comparing: floor boards and tier tables
+#[derive(Debug)]
+pub struct PlayerRecords {
+    pub name: String,
+    pub boards: Vec<UserFloorRecordBoard>,
+    pub tier_tables: Vec<UserTierRecordTable>,
+}
+
+impl PlayerRecords {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            boards: Vec::new(),
+            tier_tables: Vec::new(),
+        }
+    }
+
+    /// Load boards (for each button mode and board type) and tier tables (for each button mode)
+    pub fn load(
+        client: &Client,
+        name: &str,
+        buttons: &[u8],
+        board_types: &[&str],
+    ) -> Result<Self, APIError> {
+        let mut player = Self::new(name);
+        for button in buttons {
+            for board_type in board_types {
+                player
+                    .boards
+                    .push(client.load_user_floor_board(name, *button, board_type)?);
+            }
+            player
+                .tier_tables
+                .push(client.load_user_tier(name, *button)?);
+        }
+        Ok(player)
+    }
+
+    /// A rating counted for tier points (`0.0` if the chart isn't on top records)
+    pub fn tier_contribution(&self, chart: &ChartRef) -> f64 {
+        self.tier_tables
+            .iter()
+            .find_map(|t| t.record(chart))
+            .and_then(|r| r.user_rating)
+            .unwrap_or(0.0)
+    }
+
+    /// Played records, one for each chart. Records on boards go first, then on tier tables.
+    fn played(&self, board_type: Option<&FloorBoardType>) -> HashMap<ChartRef, &UserChartRecord> {
+        let on_boards = self
+            .boards
+            .iter()
+            .filter(|b| board_type.is_none_or(|t| &b.board_type == t))
+            .flat_map(|b| b.floors.iter())
+            .flat_map(|f| f.records.iter());
+        let on_tiers = self
+            .tier_tables
+            .iter()
+            .filter(|_| board_type.is_none())
+            .flat_map(|t| t.top_records.iter());
+
+        let mut records = HashMap::new();
+        for r in on_boards.chain(on_tiers).filter(|r| r.acc_rate.is_some()) {
+            records.entry(r.chart_ref()).or_insert(r);
+        }
+        records
+    }
+}
+
+/// Conditions for `head_to_head()`
+#[derive(Debug, Clone)]
+pub struct VersusFilter {
+    /// Only records on boards of the type (records on tier tables are not counted)
+    pub board_type: Option<FloorBoardType>,
+    pub button: Option<ButtonMode>,
+    /// Only charts in the level range, as `(min, max)`
+    pub levels: Option<(u8, u8)>,
+    /// Song categories to compare (empty for all)
+    pub categories: Vec<SongCatagory>,
+}
+
+impl VersusFilter {
+    pub fn new() -> Self {
+        Self {
+            board_type: None,
+            button: None,
+            levels: None,
+            categories: Vec::new(),
+        }
+    }
+
+    fn matches(&self, record: &UserChartRecord, catalog: &Catalog) -> bool {
+        let found = catalog.chart(&record.chart_ref());
+        if self.button.as_ref().is_some_and(|b| &record.button != b) {
+            return false;
+        }
+        if let Some((min, max)) = self.levels {
+            let level = found.map(|(_, c)| c.level).or(record.chart_level);
+            if !level.is_some_and(|l| (min..=max).contains(&l)) {
+                return false;
+            }
+        }
+        if !self.categories.is_empty() {
+            let category = found.map(|(s, _)| &s.song_cat).or(record.song_cat.as_ref());
+            if !category.is_some_and(|c| self.categories.contains(c)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Default for VersusFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A result on a chart, for the left player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Tie,
+}
+
+/// A chart both players played
+#[derive(Debug, Clone)]
+pub struct ChartComparison<'a> {
+    pub chart: ChartRef,
+    pub left: &'a UserChartRecord,
+    pub right: &'a UserChartRecord,
+    pub outcome: Outcome,
+    /// Left accuracy minus right accuracy
+    pub acc_diff: f64,
+    /// Left tier contribution minus right one
+    pub tier_diff: f64,
+}
+
+/// A comparison of two players, from `head_to_head()`
+#[derive(Debug, Clone)]
+pub struct HeadToHead<'a> {
+    /// Charts both played, in order of `ChartRef`
+    pub charts: Vec<ChartComparison<'a>>,
+    /// Charts only the left player played
+    pub only_left: Vec<&'a UserChartRecord>,
+    /// Charts only the right player played
+    pub only_right: Vec<&'a UserChartRecord>,
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+    /// Tier contributions on every filtered chart (with ones only a player played),
+    /// as `(left, right)`
+    pub tier_points: (f64, f64),
+}
+
+impl<'a> HeadToHead<'a> {
+    /// Mean of accuracy differences (`None` without charts both played)
+    pub fn mean_acc_diff(&self) -> Option<f64> {
+        if self.charts.is_empty() {
+            None
+        } else {
+            Some(self.charts.iter().map(|c| c.acc_diff).sum::<f64>() / self.charts.len() as f64)
+        }
+    }
+
+    /// Charts both played, from the biggest win for the left player
+    pub fn by_acc_diff(&self) -> Vec<&ChartComparison<'a>> {
+        let mut charts: Vec<&ChartComparison<'a>> = self.charts.iter().collect();
+        charts.sort_by(|a, b| b.acc_diff.total_cmp(&a.acc_diff));
+        charts
+    }
+}
+
+/// Compare two players on charts, matched by `(song_id, button, chart_type)`.
+///
+/// A player wins a chart with higher accuracy, or with MAX COMBO on the same accuracy.
+/// Levels and categories for the filter come from the catalog (or records, for charts not on it).
+/// ## Example
+/// ```no_run
+/// # use v_archive_rs::{head_to_head, Catalog, Client, PlayerRecords, VersusFilter};
+/// #
+/// # fn main() {
+/// let client = Client::new();
+/// let catalog = client.catalog().unwrap();
+/// let me = PlayerRecords::load(&client, "내꺼", &[6], &["SC"]).unwrap();
+/// let rival = PlayerRecords::load(&client, "라이벌", &[6], &["SC"]).unwrap();
+///
+/// let mut filter = VersusFilter::new();
+/// filter.levels = Some((14, 15));
+///
+/// let result = head_to_head(&me, &rival, &catalog, &filter);
+/// println!("{}W {}L {}D", result.wins, result.losses, result.ties);
+/// # }
+/// ```
+pub fn head_to_head<'a>(
+    left: &'a PlayerRecords,
+    right: &'a PlayerRecords,
+    catalog: &Catalog,
+    filter: &VersusFilter,
+) -> HeadToHead<'a> {
+    let filtered = |player: &'a PlayerRecords| {
+        let mut records: Vec<(ChartRef, &'a UserChartRecord)> = player
+            .played(filter.board_type.as_ref())
+            .into_iter()
+            .filter(|(_, r)| filter.matches(r, catalog))
+            .collect();
+        records.sort_by(|a, b| a.0.cmp(&b.0));
+        records
+    };
+    let left_records = filtered(left);
+    let right_records: HashMap<ChartRef, &'a UserChartRecord> =
+        filtered(right).into_iter().collect();
+
+    let mut result = HeadToHead {
+        charts: Vec::new(),
+        only_left: Vec::new(),
+        only_right: Vec::new(),
+        wins: 0,
+        losses: 0,
+        ties: 0,
+        tier_points: (0.0, 0.0),
+    };
+
+    for (chart, l) in &left_records {
+        result.tier_points.0 += left.tier_contribution(chart);
+        let r = match right_records.get(chart) {
+            Some(r) => *r,
+            None => {
+                result.only_left.push(l);
+                continue;
+            }
+        };
+
+        let acc_diff = l.acc_rate.unwrap_or(0.0) - r.acc_rate.unwrap_or(0.0);
+        let outcome = match acc_diff
+            .partial_cmp(&0.0)
+            .unwrap_or(Ordering::Equal)
+            .then(l.is_max_combo.cmp(&r.is_max_combo))
+        {
+            Ordering::Greater => {
+                result.wins += 1;
+                Outcome::Win
+            }
+            Ordering::Less => {
+                result.losses += 1;
+                Outcome::Loss
+            }
+            Ordering::Equal => {
+                result.ties += 1;
+                Outcome::Tie
+            }
+        };
+        result.charts.push(ChartComparison {
+            chart: chart.clone(),
+            left: l,
+            right: r,
+            outcome,
+            acc_diff,
+            tier_diff: left.tier_contribution(chart) - right.tier_contribution(chart),
+        });
+    }
+
+    let mut right_records: Vec<(ChartRef, &'a UserChartRecord)> =
+        right_records.into_iter().collect();
+    right_records.sort_by(|a, b| a.0.cmp(&b.0));
+    for (chart, r) in right_records {
+        result.tier_points.1 += right.tier_contribution(&chart);
+        if left_records
+            .binary_search_by(|(c, _)| c.cmp(&chart))
+            .is_err()
+        {
+            result.only_right.push(r);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{record, songs};
+    use crate::ChartType;
+
+    fn played(song_id: usize, chart_type: ChartType, acc: f64, rating: f64) -> UserChartRecord {
+        record(song_id, chart_type)
+            .with_acc(acc)
+            .with_rating(rating)
+    }
+
+    fn player(name: &str, records: Vec<UserChartRecord>) -> PlayerRecords {
+        let mut player = PlayerRecords::new(name);
+        let mut table = UserTierRecordTable::new();
+        table.top_records = records;
+        player.tier_tables.push(table);
+        player
+    }
+
+    #[test]
+    fn head_to_head_result() {
+        let catalog = Catalog::new(songs());
+        let left = player(
+            "left",
+            vec![
+                played(11, ChartType::Sc, 99.0, 200.0),
+                played(15, ChartType::Sc, 98.0, 190.0),
+                played(16, ChartType::Sc, 97.0, 180.0),
+                played(11, ChartType::Normal, 100.0, 50.0),
+            ],
+        );
+        let right = player(
+            "right",
+            vec![
+                played(11, ChartType::Sc, 98.5, 198.0),
+                played(15, ChartType::Sc, 98.0, 190.0),
+                played(11, ChartType::Normal, 100.0, 50.0),
+            ],
+        );
+
+        let result = head_to_head(&left, &right, &catalog, &VersusFilter::new());
+        assert_eq!((result.wins, result.losses, result.ties), (1, 0, 2));
+        assert_eq!(result.only_left.len(), 1);
+        assert_eq!(result.only_left[0].song_id, 16);
+        assert!(result.only_right.is_empty());
+        assert_eq!(result.tier_points, (620.0, 438.0));
+        assert_eq!(result.by_acc_diff()[0].outcome, Outcome::Win);
+        assert_eq!(result.by_acc_diff()[0].tier_diff, 2.0);
+
+        let mut filter = VersusFilter::new();
+        filter.levels = Some((13, 13));
+        filter.categories = vec![SongCatagory::from("R")];
+        let result = head_to_head(&left, &right, &catalog, &filter);
+        assert_eq!(result.charts.len(), 1);
+        assert_eq!(
+            result.charts[0].chart,
+            ChartRef::new(11, ButtonMode::Six, ChartType::Sc)
+        );
+        assert!((result.mean_acc_diff().unwrap() - 0.5).abs() < 1e-9);
+
+        filter = VersusFilter::new();
+        filter.board_type = Some(FloorBoardType::Sc);
+        let result = head_to_head(&left, &right, &catalog, &filter);
+        assert!(result.charts.is_empty());
+        assert_eq!(result.mean_acc_diff(), None);
+    }
+
+    #[test]
+    fn head_to_head_max_combo() {
+        let catalog = Catalog::new(songs());
+        let left = player(
+            "left",
+            vec![played(11, ChartType::Sc, 99.0, 200.0).with_max_combo(true)],
+        );
+        let right = player("right", vec![played(11, ChartType::Sc, 99.0, 200.0)]);
+
+        let result = head_to_head(&left, &right, &catalog, &VersusFilter::new());
+        assert_eq!((result.wins, result.losses, result.ties), (1, 0, 0));
+        assert_eq!(result.charts[0].acc_diff, 0.0);
+
+        let result = head_to_head(&right, &left, &catalog, &VersusFilter::new());
+        assert_eq!(result.charts[0].outcome, Outcome::Loss);
+    }
+}